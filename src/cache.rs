@@ -0,0 +1,425 @@
+use crate::crates_io::{API_URL, canonical_name};
+use crate::http_client::{GetRequest, Response};
+use anyhow::Context;
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+#[cfg_attr(test, autospy::autospy)]
+pub trait CacheStore {
+    fn read(&self, key: &str, ttl: Duration) -> Option<String>;
+    fn write(&self, key: &str, contents: &str) -> anyhow::Result<()>;
+}
+
+static PARTIAL_WRITES: AtomicU64 = AtomicU64::new(0);
+
+/// Stores each response in its own file, entries expire by the file's modified time
+pub struct DiskCache {
+    dir: PathBuf,
+}
+
+impl DiskCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+}
+
+impl CacheStore for DiskCache {
+    fn read(&self, key: &str, ttl: Duration) -> Option<String> {
+        let path = self.dir.join(key);
+        let age = SystemTime::now()
+            .duration_since(path.metadata().ok()?.modified().ok()?)
+            .unwrap_or_default();
+
+        match age < ttl {
+            true => std::fs::read_to_string(path).ok(),
+            false => None,
+        }
+    }
+
+    fn write(&self, key: &str, contents: &str) -> anyhow::Result<()> {
+        std::fs::create_dir_all(&self.dir).context("failed to create cache directory")?;
+        // written then renamed so concurrent runs never read a partial entry,
+        // each write has its own partial file as jobs of a run can write the same key
+        let partial = self.dir.join(format!(
+            "{key}.{}.{}",
+            std::process::id(),
+            PARTIAL_WRITES.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::write(&partial, contents).context("failed to write cache entry")?;
+        std::fs::rename(partial, self.dir.join(key)).context("failed to write cache entry")
+    }
+}
+
+/// Serves responses from the store while they are younger than the ttl, failed requests are not cached
+pub struct CachedClient<C, S> {
+    client: C,
+    store: S,
+    ttl: Duration,
+}
+
+impl<C: GetRequest, S: CacheStore> CachedClient<C, S> {
+    pub fn new(client: C, store: S, ttl: Duration) -> Self {
+        Self { client, store, ttl }
+    }
+
+    /// Only requests the crates not cached, as a list of other crates is keyed by another url
+    fn get_crate_list(&self, url: &str, crate_names: Vec<&str>) -> anyhow::Result<String> {
+        let mut crates = Vec::new();
+        let mut uncached = Vec::new();
+        for crate_name in crate_names {
+            match self
+                .store
+                .read(&crate_key(crate_name), self.ttl)
+                .and_then(|record| serde_json::from_str::<Value>(&record).ok())
+            {
+                Some(record) => crates.push(record),
+                None => uncached.push(crate_name),
+            }
+        }
+
+        if !uncached.is_empty() {
+            let response = self.client.get(&list_url(url, &uncached))?;
+            // left for the caller to fail on when it isn't a list of crates
+            let Some(Value::Array(fetched)) = serde_json::from_str::<Value>(&response)
+                .ok()
+                .and_then(|mut list| list.get_mut("crates").map(Value::take))
+            else {
+                return Ok(response);
+            };
+            for record in fetched {
+                if let Some(crate_name) = record["name"].as_str() {
+                    let _ = self
+                        .store
+                        .write(&crate_key(crate_name), &record.to_string());
+                }
+                crates.push(record);
+            }
+        }
+
+        Ok(serde_json::json!({ "crates": crates }).to_string())
+    }
+}
+
+impl<C: GetRequest, S: CacheStore> GetRequest for CachedClient<C, S> {
+    fn get(&self, url: &str) -> Result<String, anyhow::Error> {
+        // crates.io lists are cached a crate at a time so differently chunked lists reuse them
+        if let Some(crate_names) = listed_crates(url) {
+            return self.get_crate_list(url, crate_names);
+        }
+
+        let key = cache_key(url);
+
+        if let Some(response) = self.store.read(&key, self.ttl) {
+            return Ok(response);
+        }
+
+        let response = self.client.get(url)?;
+        // the response is still usable if it can't be cached
+        let _ = self.store.write(&key, &response);
+        Ok(response)
+    }
+
+    fn get_response(&self, url: &str) -> Result<Response, anyhow::Error> {
+        // kept apart from the body only entry of the same url
+        let key = cache_key(&format!("response {url}"));
+
+        if let Some(response) = self
+            .store
+            .read(&key, self.ttl)
+            .and_then(|response| serde_json::from_str(&response).ok())
+        {
+            return Ok(response);
+        }
+
+        let response = self.client.get_response(url)?;
+        if let Ok(entry) = serde_json::to_string(&response) {
+            let _ = self.store.write(&key, &entry);
+        }
+        Ok(response)
+    }
+}
+
+// fnv-1a, stable across runs and platforms unlike std's hasher
+fn cache_key(url: &str) -> String {
+    let hash = url.bytes().fold(0xcbf29ce484222325, |hash: u64, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{hash:016x}")
+}
+
+/// The crates of a crates.io list request, by its `ids[]` parameters
+fn listed_crates(url: &str) -> Option<Vec<&str>> {
+    let crate_names = url
+        .strip_prefix(API_URL)?
+        .strip_prefix('?')?
+        .split('&')
+        .filter_map(|pair| pair.strip_prefix("ids[]="))
+        .collect::<Vec<_>>();
+    (!crate_names.is_empty()).then_some(crate_names)
+}
+
+/// The list request for only the given crates, keeping the other parameters
+fn list_url(url: &str, crate_names: &[&str]) -> String {
+    let (base, query) = url.split_once('?').unwrap_or((url, ""));
+    let query = query
+        .split('&')
+        .filter(|pair| {
+            pair.strip_prefix("ids[]=")
+                .is_none_or(|crate_name| crate_names.contains(&crate_name))
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    format!("{base}?{query}")
+}
+
+fn crate_key(crate_name: &str) -> String {
+    cache_key(&format!("crate {}", canonical_name(crate_name)))
+}
+
+pub fn default_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CACHE_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => match std::env::var_os("LOCALAPPDATA") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(std::env::var_os("HOME")?).join(".cache"),
+        },
+    };
+    Some(base.join("trust-list"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cache::{CacheStore, CacheStoreSpy, CachedClient, DiskCache, cache_key};
+    use crate::http_client::{GetRequest, GetRequestSpy, Response};
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    const TTL: Duration = Duration::from_secs(60);
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("trust-list-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn cached_response_is_returned_without_request() {
+        let http_client_spy = GetRequestSpy::default();
+        let store_spy = CacheStoreSpy::default();
+
+        store_spy.read.returns.set([Some("cached".to_string())]);
+
+        let client = CachedClient::new(http_client_spy.clone(), store_spy.clone(), TTL);
+
+        assert_eq!(
+            "cached",
+            client
+                .get("https://crates.io/api/v1/crates/autospy")
+                .unwrap()
+        );
+        assert_eq!(
+            [(cache_key("https://crates.io/api/v1/crates/autospy"), TTL)],
+            store_spy.read.arguments
+        );
+        assert!(http_client_spy.get.arguments.take().is_empty())
+    }
+
+    #[test]
+    fn uncached_response_is_requested_and_stored() {
+        let http_client_spy = GetRequestSpy::default();
+        let store_spy = CacheStoreSpy::default();
+
+        store_spy.read.returns.set([None]);
+        http_client_spy
+            .get
+            .returns
+            .set([Ok("response".to_string())]);
+        store_spy.write.returns.set([Ok(())]);
+
+        let client = CachedClient::new(http_client_spy, store_spy.clone(), TTL);
+
+        assert_eq!(
+            "response",
+            client
+                .get("https://crates.io/api/v1/crates/autospy")
+                .unwrap()
+        );
+        assert_eq!(
+            [(
+                cache_key("https://crates.io/api/v1/crates/autospy"),
+                "response".to_string()
+            )],
+            store_spy.write.arguments
+        )
+    }
+
+    #[test]
+    fn failed_request_is_not_stored() {
+        let http_client_spy = GetRequestSpy::default();
+        let store_spy = CacheStoreSpy::default();
+
+        store_spy.read.returns.set([None]);
+        http_client_spy
+            .get
+            .returns
+            .set([Err(anyhow::anyhow!("deliberate test error"))]);
+
+        let client = CachedClient::new(http_client_spy, store_spy.clone(), TTL);
+
+        assert_eq!(
+            "deliberate test error",
+            client
+                .get("https://crates.io/api/v1/crates/autospy")
+                .unwrap_err()
+                .to_string()
+        );
+        assert!(store_spy.write.arguments.take().is_empty())
+    }
+
+    #[test]
+    fn failing_to_store_response_still_returns_it() {
+        let http_client_spy = GetRequestSpy::default();
+        let store_spy = CacheStoreSpy::default();
+
+        store_spy.read.returns.set([None]);
+        http_client_spy
+            .get
+            .returns
+            .set([Ok("response".to_string())]);
+        store_spy
+            .write
+            .returns
+            .set([Err(anyhow::anyhow!("deliberate test error"))]);
+
+        let client = CachedClient::new(http_client_spy, store_spy, TTL);
+
+        assert_eq!(
+            "response",
+            client
+                .get("https://crates.io/api/v1/crates/autospy")
+                .unwrap()
+        )
+    }
+
+    #[test]
+    fn response_with_headers_is_cached_separately_from_body() {
+        let http_client_spy = GetRequestSpy::default();
+        let store_spy = CacheStoreSpy::default();
+        let response = Response {
+            body: "[1]".to_string(),
+            headers: BTreeMap::from([("link".to_string(), "<url>; rel=\"last\"".to_string())]),
+        };
+
+        store_spy
+            .read
+            .returns
+            .set([None, Some(serde_json::to_string(&response).unwrap())]);
+        http_client_spy.get_response.returns.set([Ok(Response {
+            body: "[1]".to_string(),
+            headers: response.headers.clone(),
+        })]);
+        store_spy.write.returns.set([Ok(())]);
+
+        let client = CachedClient::new(http_client_spy, store_spy.clone(), TTL);
+        let url = "https://api.github.com/repos/lhalf/autospy/contributors?per_page=1";
+
+        assert_eq!(response, client.get_response(url).unwrap());
+        assert_eq!(response, client.get_response(url).unwrap());
+
+        let [(key, _)] = store_spy.write.arguments.take().try_into().unwrap();
+        assert_ne!(cache_key(url), key)
+    }
+
+    #[test]
+    fn overlapping_crate_lists_only_request_uncached_crates() {
+        let dir = temp_dir("crate-lists");
+        let http_client_spy = GetRequestSpy::default();
+
+        http_client_spy.get.returns.set([
+            Ok(
+                r#"{"crates":[{"name":"anyhow"},{"name":"autospy"}],"meta":{"total":2}}"#
+                    .to_string(),
+            ),
+            Ok(r#"{"crates":[{"name":"clap"}],"meta":{"total":1}}"#.to_string()),
+        ]);
+
+        let client = CachedClient::new(http_client_spy.clone(), DiskCache::new(dir.clone()), TTL);
+
+        client
+            .get("https://crates.io/api/v1/crates?ids[]=anyhow&ids[]=autospy&per_page=50")
+            .unwrap();
+
+        assert_eq!(
+            r#"{"crates":[{"name":"autospy"},{"name":"clap"}]}"#,
+            client
+                .get("https://crates.io/api/v1/crates?ids[]=autospy&ids[]=clap&per_page=50")
+                .unwrap()
+        );
+        assert_eq!(
+            [
+                "https://crates.io/api/v1/crates?ids[]=anyhow&ids[]=autospy&per_page=50"
+                    .to_string(),
+                "https://crates.io/api/v1/crates?ids[]=clap&per_page=50".to_string()
+            ],
+            http_client_spy.get.arguments
+        );
+        std::fs::remove_dir_all(dir).unwrap()
+    }
+
+    #[test]
+    fn different_urls_have_different_keys() {
+        assert_ne!(
+            cache_key("https://crates.io/api/v1/crates/autospy"),
+            cache_key("https://crates.io/api/v1/crates/autospy/reverse_dependencies")
+        )
+    }
+
+    #[test]
+    fn disk_cache_reads_written_entry_within_ttl() {
+        let dir = temp_dir("within-ttl");
+        let cache = DiskCache::new(dir.clone());
+
+        cache.write("key", "response").unwrap();
+
+        assert_eq!(Some("response".to_string()), cache.read("key", TTL));
+        std::fs::remove_dir_all(dir).unwrap()
+    }
+
+    #[test]
+    fn disk_cache_concurrent_writes_of_same_key_are_whole() {
+        let dir = temp_dir("concurrent-writes");
+        let cache = DiskCache::new(dir.clone());
+        let response = "response".repeat(10_000);
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..20 {
+                        cache.write("key", &response).unwrap();
+                        assert_eq!(Some(response.clone()), cache.read("key", TTL));
+                    }
+                });
+            }
+        });
+
+        std::fs::remove_dir_all(dir).unwrap()
+    }
+
+    #[test]
+    fn disk_cache_entry_expires_after_ttl() {
+        let dir = temp_dir("expired");
+        let cache = DiskCache::new(dir.clone());
+
+        cache.write("key", "response").unwrap();
+
+        assert_eq!(None, cache.read("key", Duration::ZERO));
+        std::fs::remove_dir_all(dir).unwrap()
+    }
+
+    #[test]
+    fn disk_cache_missing_entry_is_not_found() {
+        assert_eq!(None, DiskCache::new(temp_dir("missing")).read("key", TTL))
+    }
+}
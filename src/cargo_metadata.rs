@@ -0,0 +1,492 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::process::Command;
+
+use anyhow::Context;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Dependency {
+    pub name: String,
+    pub version: String,
+    /// The SPDX expression of the resolved version, from its manifest
+    pub license: Option<String>,
+    pub source: Source,
+    pub kinds: BTreeSet<DependencyKind>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Source {
+    Registry(String),
+    Git(String),
+    Path,
+}
+
+impl Source {
+    /// Whether the dependency was resolved from crates.io, the only registry its info is fetched from
+    pub fn is_crates_io(&self) -> bool {
+        matches!(self, Self::Registry(source) if [
+            "registry+https://github.com/rust-lang/crates.io-index",
+            "sparse+https://index.crates.io/",
+        ]
+        .contains(&source.as_str()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DependencyKind {
+    Normal,
+    Development,
+    Build,
+}
+
+pub fn dependencies(
+    depth: Option<u8>,
+    include_dev_dependencies: bool,
+    include_build_dependencies: bool,
+    excluded_workspaces: Vec<String>,
+) -> anyhow::Result<Vec<Dependency>> {
+    let mut args = vec![
+        "metadata".to_string(),
+        "--format-version".to_string(),
+        "1".to_string(),
+    ];
+
+    // matches cargo tree, which only shows dependencies for the host platform
+    if let Some(host) = host_target() {
+        args.push("--filter-platform".to_string());
+        args.push(host);
+    }
+
+    let output = Command::new("cargo")
+        .args(args)
+        .output()
+        .context("failed to call cargo metadata")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "cargo metadata failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    }
+
+    let metadata: Metadata = serde_json::from_slice(&output.stdout)
+        .context("failed to deserialize cargo metadata output")?;
+
+    Ok(resolve(
+        metadata,
+        depth,
+        include_dev_dependencies,
+        include_build_dependencies,
+        excluded_workspaces,
+    ))
+}
+
+fn host_target() -> Option<String> {
+    let output = Command::new(std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string()))
+        .arg("-vV")
+        .output()
+        .ok()?;
+
+    String::from_utf8(output.stdout)
+        .ok()?
+        .lines()
+        .find_map(|line| line.strip_prefix("host: "))
+        .map(String::from)
+}
+
+#[derive(Deserialize, Debug)]
+struct Metadata {
+    packages: Vec<Package>,
+    workspace_members: Vec<String>,
+    resolve: Resolve,
+}
+
+#[derive(Deserialize, Debug)]
+struct Package {
+    id: String,
+    name: String,
+    version: String,
+    license: Option<String>,
+    source: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Resolve {
+    nodes: Vec<Node>,
+    root: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Node {
+    id: String,
+    deps: Vec<NodeDependency>,
+}
+
+#[derive(Deserialize, Debug)]
+struct NodeDependency {
+    pkg: String,
+    dep_kinds: Vec<DependencyKindInfo>,
+}
+
+#[derive(Deserialize, Debug)]
+struct DependencyKindInfo {
+    kind: Option<String>,
+}
+
+fn resolve(
+    metadata: Metadata,
+    depth: Option<u8>,
+    include_dev_dependencies: bool,
+    include_build_dependencies: bool,
+    excluded_workspaces: Vec<String>,
+) -> Vec<Dependency> {
+    let packages: HashMap<&str, &Package> = metadata
+        .packages
+        .iter()
+        .map(|package| (package.id.as_str(), package))
+        .collect();
+
+    let nodes: HashMap<&str, &Node> = metadata
+        .resolve
+        .nodes
+        .iter()
+        .map(|node| (node.id.as_str(), node))
+        .collect();
+
+    let roots = roots(&metadata, &packages, &excluded_workspaces);
+
+    let mut kinds: BTreeMap<&str, BTreeSet<DependencyKind>> = BTreeMap::new();
+    let mut visited: BTreeSet<&str> = roots.iter().copied().collect();
+    let mut queue: VecDeque<(&str, u8)> = roots.into_iter().map(|root| (root, 0)).collect();
+
+    while let Some((id, current_depth)) = queue.pop_front() {
+        if depth.is_some_and(|depth| current_depth >= depth) {
+            continue;
+        }
+
+        for dependency in nodes
+            .get(id)
+            .map(|node| node.deps.as_slice())
+            .unwrap_or_default()
+        {
+            let edge_kinds: BTreeSet<DependencyKind> = dependency
+                .dep_kinds
+                .iter()
+                .map(|info| DependencyKind::from(info.kind.as_deref()))
+                .filter(|kind| match kind {
+                    DependencyKind::Normal => true,
+                    DependencyKind::Development => include_dev_dependencies,
+                    DependencyKind::Build => include_build_dependencies,
+                })
+                .collect();
+
+            if edge_kinds.is_empty() {
+                continue;
+            }
+
+            kinds
+                .entry(dependency.pkg.as_str())
+                .or_default()
+                .extend(edge_kinds);
+
+            if visited.insert(dependency.pkg.as_str()) {
+                queue.push_back((dependency.pkg.as_str(), current_depth + 1));
+            }
+        }
+    }
+
+    let mut dependencies: Vec<Dependency> = kinds
+        .into_iter()
+        .filter(|(id, _)| !metadata.workspace_members.iter().any(|member| member == id))
+        .filter_map(|(id, kinds)| {
+            packages.get(id).map(|package| Dependency {
+                name: package.name.clone(),
+                version: package.version.clone(),
+                license: package.license.clone(),
+                source: Source::from(package.source.as_deref()),
+                kinds,
+            })
+        })
+        .collect();
+
+    dependencies.sort();
+    dependencies
+}
+
+fn roots<'a>(
+    metadata: &'a Metadata,
+    packages: &HashMap<&str, &Package>,
+    excluded_workspaces: &[String],
+) -> Vec<&'a str> {
+    // matches cargo tree, the root package unless workspaces are excluded
+    match (&metadata.resolve.root, excluded_workspaces.is_empty()) {
+        (Some(root), true) => vec![root.as_str()],
+        _ => metadata
+            .workspace_members
+            .iter()
+            .map(String::as_str)
+            .filter(|member| {
+                packages
+                    .get(member)
+                    .is_none_or(|package| !excluded_workspaces.contains(&package.name))
+            })
+            .collect(),
+    }
+}
+
+impl From<Option<&str>> for DependencyKind {
+    fn from(kind: Option<&str>) -> Self {
+        match kind {
+            Some("dev") => Self::Development,
+            Some("build") => Self::Build,
+            _ => Self::Normal,
+        }
+    }
+}
+
+impl From<Option<&str>> for Source {
+    fn from(source: Option<&str>) -> Self {
+        match source {
+            Some(source) if source.starts_with("git+") => Self::Git(source.to_string()),
+            Some(source) => Self::Registry(source.to_string()),
+            None => Self::Path,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeSet;
+
+    use super::{Dependency, DependencyKind, Metadata, Source, dependencies, resolve};
+
+    const CRATES_IO: &str = "registry+https://github.com/rust-lang/crates.io-index";
+
+    fn names(dependencies: Vec<Dependency>) -> BTreeSet<String> {
+        dependencies
+            .into_iter()
+            .map(|dependency| dependency.name)
+            .collect()
+    }
+
+    fn metadata() -> Metadata {
+        serde_json::from_str(include_str!("../tests/data/cargo_metadata.json")).unwrap()
+    }
+
+    fn name_and_versions(dependencies: Vec<Dependency>) -> Vec<(String, String)> {
+        dependencies
+            .into_iter()
+            .map(|dependency| (dependency.name, dependency.version))
+            .collect()
+    }
+
+    #[test]
+    fn cargo_metadata_depth_1() {
+        // could get this from cargo.toml?
+        let expected_crates = BTreeSet::from([
+            "anyhow".to_string(),
+            "chrono".to_string(),
+            "clap".to_string(),
+            "csv".to_string(),
+            "field_names".to_string(),
+            "flate2".to_string(),
+            "pbr".to_string(),
+            "reqwest".to_string(),
+            "serde".to_string(),
+            "serde_json".to_string(),
+            "tar".to_string(),
+            "toml".to_string(),
+        ]);
+        assert_eq!(
+            expected_crates,
+            names(dependencies(Some(1), false, false, Vec::new()).unwrap())
+        );
+    }
+
+    #[test]
+    fn cargo_metadata_depth_1_with_dev() {
+        // could get this from cargo.toml?
+        let expected_crates = BTreeSet::from([
+            "anyhow".to_string(),
+            "autospy".to_string(),
+            "chrono".to_string(),
+            "clap".to_string(),
+            "csv".to_string(),
+            "field_names".to_string(),
+            "flate2".to_string(),
+            "pbr".to_string(),
+            "reqwest".to_string(),
+            "serde".to_string(),
+            "serde_json".to_string(),
+            "tar".to_string(),
+            "toml".to_string(),
+        ]);
+        assert_eq!(
+            expected_crates,
+            names(dependencies(Some(1), true, false, Vec::new()).unwrap())
+        );
+    }
+
+    #[test]
+    fn cargo_metadata_ignores_invalid_excluded_workspaces() {
+        // could get this from cargo.toml?
+        let expected_crates = BTreeSet::from([
+            "anyhow".to_string(),
+            "chrono".to_string(),
+            "clap".to_string(),
+            "csv".to_string(),
+            "field_names".to_string(),
+            "flate2".to_string(),
+            "pbr".to_string(),
+            "reqwest".to_string(),
+            "serde".to_string(),
+            "serde_json".to_string(),
+            "tar".to_string(),
+            "toml".to_string(),
+        ]);
+        assert_eq!(
+            expected_crates,
+            names(dependencies(Some(1), false, false, vec!["invalid".to_string()]).unwrap())
+        );
+    }
+
+    #[test]
+    fn resolves_all_normal_dependencies_of_root() {
+        assert_eq!(
+            vec![
+                Dependency {
+                    name: "local-utils".to_string(),
+                    version: "0.2.0".to_string(),
+                    license: None,
+                    source: Source::Path,
+                    kinds: BTreeSet::from([DependencyKind::Normal]),
+                },
+                Dependency {
+                    name: "pbr".to_string(),
+                    version: "1.1.1".to_string(),
+                    license: Some("MIT".to_string()),
+                    source: Source::Git(
+                        "git+https://github.com/a8m/pb?rev=abc123#abc123".to_string()
+                    ),
+                    kinds: BTreeSet::from([DependencyKind::Normal]),
+                },
+                Dependency {
+                    name: "serde".to_string(),
+                    version: "1.0.228".to_string(),
+                    license: Some("MIT OR Apache-2.0".to_string()),
+                    source: Source::Registry(CRATES_IO.to_string()),
+                    kinds: BTreeSet::from([DependencyKind::Normal]),
+                },
+                Dependency {
+                    name: "serde_json".to_string(),
+                    version: "1.0.145".to_string(),
+                    license: Some("MIT OR Apache-2.0".to_string()),
+                    source: Source::Registry(CRATES_IO.to_string()),
+                    kinds: BTreeSet::from([DependencyKind::Normal]),
+                },
+                Dependency {
+                    name: "syn".to_string(),
+                    version: "1.0.109".to_string(),
+                    license: Some("MIT OR Apache-2.0".to_string()),
+                    source: Source::Registry(CRATES_IO.to_string()),
+                    kinds: BTreeSet::from([DependencyKind::Normal]),
+                },
+                Dependency {
+                    name: "syn".to_string(),
+                    version: "2.0.106".to_string(),
+                    license: Some("MIT OR Apache-2.0".to_string()),
+                    source: Source::Registry(CRATES_IO.to_string()),
+                    kinds: BTreeSet::from([DependencyKind::Normal]),
+                },
+            ],
+            resolve(metadata(), None, false, false, Vec::new())
+        )
+    }
+
+    #[test]
+    fn only_crates_io_sources_are_from_crates_io() {
+        assert_eq!(
+            [true, true, false, false, false],
+            [
+                Source::Registry(CRATES_IO.to_string()),
+                Source::Registry("sparse+https://index.crates.io/".to_string()),
+                Source::Registry("sparse+https://example.com/index/".to_string()),
+                Source::Git("git+https://github.com/a8m/pb?rev=abc123#abc123".to_string()),
+                Source::Path,
+            ]
+            .map(|source| source.is_crates_io())
+        )
+    }
+
+    #[test]
+    fn resolves_dependencies_to_depth() {
+        assert_eq!(
+            vec![
+                ("serde_json".to_string(), "1.0.145".to_string()),
+                ("syn".to_string(), "2.0.106".to_string()),
+            ],
+            name_and_versions(resolve(metadata(), Some(1), false, false, Vec::new()))
+        )
+    }
+
+    #[test]
+    fn resolves_dev_and_build_dependencies_with_edge_kinds() {
+        let dependencies = resolve(metadata(), Some(1), true, true, Vec::new());
+
+        assert_eq!(
+            vec![
+                (
+                    "autospy".to_string(),
+                    BTreeSet::from([DependencyKind::Development])
+                ),
+                ("cc".to_string(), BTreeSet::from([DependencyKind::Build])),
+                (
+                    "serde_json".to_string(),
+                    BTreeSet::from([DependencyKind::Normal])
+                ),
+                (
+                    "syn".to_string(),
+                    BTreeSet::from([DependencyKind::Normal, DependencyKind::Build])
+                ),
+            ],
+            dependencies
+                .into_iter()
+                .map(|dependency| (dependency.name, dependency.kinds))
+                .collect::<Vec<_>>()
+        )
+    }
+
+    #[test]
+    fn excluding_workspace_resolves_remaining_members() {
+        assert_eq!(
+            vec![
+                ("local-utils".to_string(), "0.2.0".to_string()),
+                ("pbr".to_string(), "1.1.1".to_string()),
+                ("serde_json".to_string(), "1.0.145".to_string()),
+                ("syn".to_string(), "1.0.109".to_string()),
+                ("syn".to_string(), "2.0.106".to_string()),
+            ],
+            name_and_versions(resolve(
+                metadata(),
+                Some(1),
+                false,
+                false,
+                vec!["invalid".to_string()]
+            ))
+        );
+        assert_eq!(
+            vec![
+                ("local-utils".to_string(), "0.2.0".to_string()),
+                ("pbr".to_string(), "1.1.1".to_string()),
+                ("syn".to_string(), "1.0.109".to_string()),
+            ],
+            name_and_versions(resolve(
+                metadata(),
+                Some(1),
+                false,
+                false,
+                vec!["app".to_string()]
+            ))
+        )
+    }
+}
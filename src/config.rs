@@ -0,0 +1,89 @@
+use crate::policy::Policy;
+use anyhow::Context;
+use std::path::Path;
+
+/// The policy file, its thresholds are checked by --check and its columns are used unless --columns is given
+#[derive(Debug, Default, PartialEq)]
+pub struct Config {
+    pub columns: Option<Vec<String>>,
+    pub policy: Policy,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read policy file: {}", path.display()))?;
+        Self::parse(&contents)
+            .with_context(|| format!("failed to parse policy file: {}", path.display()))
+    }
+
+    fn parse(contents: &str) -> anyhow::Result<Self> {
+        let mut table: toml::Table = toml::from_str(contents)?;
+        // the remaining keys are thresholds, so unknown keys are still rejected
+        let columns = table
+            .remove("columns")
+            .map(|columns| columns.try_into())
+            .transpose()?;
+        Ok(Self {
+            columns,
+            policy: table.try_into()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Config;
+    use crate::policy::Policy;
+
+    #[test]
+    fn parses_columns_and_thresholds() {
+        let config = Config::parse(
+            r#"
+            columns = ["name", "version", "downloads"]
+            min_downloads = 1000
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Some(vec![
+                "name".to_string(),
+                "version".to_string(),
+                "downloads".to_string()
+            ]),
+            config.columns
+        );
+        assert_ne!(Policy::default(), config.policy)
+    }
+
+    #[test]
+    fn empty_config_has_no_columns_or_thresholds() {
+        assert_eq!(Config::default(), Config::parse("").unwrap())
+    }
+
+    #[test]
+    fn columns_must_be_a_list_of_names() {
+        assert!(Config::parse("columns = \"name,version\"").is_err())
+    }
+
+    #[test]
+    fn unknown_key_fails_to_parse() {
+        assert!(
+            Config::parse("min_stars = 10")
+                .unwrap_err()
+                .to_string()
+                .contains("unknown field `min_stars`")
+        )
+    }
+
+    #[test]
+    fn missing_policy_file_fails_to_load() {
+        assert_eq!(
+            "failed to read policy file: does/not/exist.toml",
+            Config::load("does/not/exist.toml".as_ref())
+                .unwrap_err()
+                .to_string()
+        )
+    }
+}
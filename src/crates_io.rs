@@ -1,55 +1,103 @@
+use crate::fetched::Fetched;
 use crate::http_client::GetRequest;
+use crate::markdown;
+use crate::output_format::csv_record;
 use anyhow::Context;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, SecondsFormat, Utc};
 use field_names::FieldNames;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-const API_URL: &str = "https://crates.io/api/v1/crates";
+pub const API_URL: &str = "https://crates.io/api/v1/crates";
+/// Crates requested at once, crates.io returns at most 100 per page
+pub const BATCH_SIZE: usize = 50;
+
+#[derive(Deserialize, Debug)]
+struct Crates {
+    /// Decoded a record at a time, so a record crates.io changed only fails its own crate
+    crates: Vec<serde_json::Value>,
+}
+
+/// The links crates.io has besides the repository, used in its place when there is none
 #[derive(Deserialize, Debug)]
-pub struct CrateInfo {
-    #[serde(rename = "crate")]
+struct CrateRecord {
+    #[serde(flatten)]
     _crate: Crate,
+    homepage: Option<String>,
+    documentation: Option<String>,
 }
 
-#[derive(Deserialize, Debug, PartialEq, FieldNames)]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, FieldNames)]
 pub struct Crate {
     pub name: String,
+    #[serde(skip_deserializing)]
+    pub version: String,
+    #[serde(rename(deserialize = "max_stable_version"))]
+    pub latest_version: Option<String>,
+    #[serde(skip_deserializing)]
+    pub license: Option<String>,
     pub downloads: u64,
-    #[serde(skip)]
-    pub contributors: u16,
-    #[serde(skip)]
+    #[serde(skip_deserializing)]
+    pub contributors: Fetched<u16>,
+    #[serde(skip_deserializing)]
     pub reverse_dependencies: u64,
-    pub versions: Vec<u64>,
+    #[serde(rename(deserialize = "num_versions"))]
+    pub versions: u64,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
-    pub repository: String,
+    #[serde(default)]
+    pub repository: Fetched<String>,
+    #[serde(skip_deserializing)]
+    pub fetched_at: DateTime<Utc>,
 }
 
 impl Crate {
-    pub fn table_heading() -> String {
-        ["|", &Self::FIELDS.join("|"), "|\n"].join("")
+    /// The generated columns, in the order of the cells
+    pub fn columns() -> &'static [&'static str] {
+        &Self::FIELDS
     }
 
-    pub fn table_divider() -> String {
-        ["|", &vec!["-"; Self::FIELDS.len()].join("|"), "|\n"].join("")
+    pub fn table_entry(&self) -> String {
+        markdown::row(&self.table_cells())
     }
 
-    pub fn table_entry(&self) -> String {
-        format!(
-            "|{}|{}|{}|{}|{}|{}|{}|{}|\n",
-            self.name,
-            self.downloads,
-            if self.contributors >= 30 {
-                "30+".to_string()
-            } else {
-                self.contributors.to_string()
-            },
-            self.reverse_dependencies,
-            self.versions.len(),
-            self.created_at.format("%d/%m/%Y"),
-            self.updated_at.format("%d/%m/%Y"),
-            self.repository
-        )
+    /// The markdown cell for each column
+    pub fn table_cells(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.version.clone(),
+            self.latest_version.clone().unwrap_or_default(),
+            self.license.clone().unwrap_or_default(),
+            self.downloads.to_string(),
+            self.contributors.table_cell(),
+            self.reverse_dependencies.to_string(),
+            self.versions.to_string(),
+            self.created_at.format("%d/%m/%Y").to_string(),
+            self.updated_at.format("%d/%m/%Y").to_string(),
+            self.repository.table_cell(),
+            self.fetched_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+        ]
+    }
+
+    pub fn csv_entry(&self) -> String {
+        csv_record(&self.csv_cells())
+    }
+
+    /// The csv field for each column
+    pub fn csv_cells(&self) -> Vec<String> {
+        vec![
+            self.name.clone(),
+            self.version.clone(),
+            self.latest_version.clone().unwrap_or_default(),
+            self.license.clone().unwrap_or_default(),
+            self.downloads.to_string(),
+            self.contributors.csv_field(),
+            self.reverse_dependencies.to_string(),
+            self.versions.to_string(),
+            self.created_at.format("%Y-%m-%d").to_string(),
+            self.updated_at.format("%Y-%m-%d").to_string(),
+            self.repository.csv_field(),
+            self.fetched_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+        ]
     }
 }
 
@@ -63,30 +111,53 @@ struct Meta {
     total: u64,
 }
 
-pub fn get_crate_info(
-    http_client: &impl GetRequest,
-    crate_name: &str,
-) -> Result<Crate, anyhow::Error> {
-    // crates.io api policy - https://crates.io/data-access#api
-    #[cfg(not(test))]
-    std::thread::sleep(std::time::Duration::from_secs(1));
-
-    let url = format!("{API_URL}/{crate_name}");
+/// Each crate by name, or the error its record couldn't be deserialized with
+pub type CrateResults = Vec<(String, Result<Crate, anyhow::Error>)>;
 
-    let mut crate_info: CrateInfo = serde_json::from_str(&http_client.get(&url)?)
+/// Fetches the named crates in a single request, the version and license are left to the caller
+/// and crates crates.io does not have are left out
+pub fn get_crates(
+    http_client: &impl GetRequest,
+    crate_names: &[&str],
+) -> Result<CrateResults, anyhow::Error> {
+    let ids = crate_names
+        .iter()
+        .map(|crate_name| format!("ids[]={crate_name}"))
+        .collect::<Vec<_>>()
+        .join("&");
+    let url = format!("{API_URL}?{ids}&per_page={BATCH_SIZE}");
+
+    let crates: Crates = serde_json::from_str(&http_client.get(&url)?)
         .with_context(|| format!("failed to deserialize response from: {url}"))?;
 
-    // crates.io treats - and _ the same, set crate name to cargo tree name
-    // so when appending we don't get the name again
-    crate_info._crate.name = crate_name.to_string();
-
-    crate_info._crate.reverse_dependencies = get_reverse_dependencies(http_client, crate_name)
-        .with_context(|| format!("failed to get reverse dependencies for {crate_name}"))?;
-
-    Ok(crate_info._crate)
+    Ok(crates
+        .crates
+        .into_iter()
+        // a record without a name can't be told apart, its crate is then not found
+        .filter_map(|record| {
+            let name = record.get("name")?.as_str()?.to_string();
+            let crate_info = serde_json::from_value(record)
+                .map(
+                    |CrateRecord {
+                         _crate: mut crate_info,
+                         homepage,
+                         documentation,
+                     }| {
+                        if crate_info.repository == Fetched::Absent {
+                            crate_info.repository = homepage
+                                .or(documentation)
+                                .map_or(Fetched::Absent, Fetched::Present);
+                        }
+                        crate_info
+                    },
+                )
+                .with_context(|| format!("failed to deserialize {name} from: {url}"));
+            Some((name, crate_info))
+        })
+        .collect())
 }
 
-fn get_reverse_dependencies(
+pub fn get_reverse_dependencies(
     http_client: &impl GetRequest,
     crate_name: &str,
 ) -> Result<u64, anyhow::Error> {
@@ -99,64 +170,136 @@ fn get_reverse_dependencies(
     Ok(reverse_dependencies.meta.total)
 }
 
+/// crates.io treats - and _, and upper and lower case, the same
+pub fn canonical_name(crate_name: &str) -> String {
+    crate_name.to_lowercase().replace('-', "_")
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::crates_io::{Crate, get_crate_info, get_reverse_dependencies};
-    use crate::http_client::GetRequestSpy;
+    use crate::crates_io::{Crate, canonical_name, get_crates, get_reverse_dependencies};
+    use crate::fetched::Fetched;
+    use crate::http_client::{GetRequest, GetRequestSpy};
     use chrono::DateTime;
     use std::str::FromStr;
 
     #[test]
-    fn produces_expected_table_headings() {
+    fn produces_expected_table_line() {
         assert_eq!(
-            "|name|downloads|contributors|reverse_dependencies|versions|created_at|updated_at|repository|\n",
-            Crate::table_heading()
+            "|example|1.0.0|1.2.0|MIT OR Apache-2.0|100|20|10|2|01/01/1970|01/01/1970|https://github.com/lhalf/trust-list|1970-01-01T00:00:00Z|\n",
+            Crate {
+                name: "example".to_string(),
+                version: "1.0.0".to_string(),
+                latest_version: Some("1.2.0".to_string()),
+                license: Some("MIT OR Apache-2.0".to_string()),
+                downloads: 100,
+                contributors: Fetched::Present(20),
+                reverse_dependencies: 10,
+                versions: 2,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+                repository: Fetched::Present("https://github.com/lhalf/trust-list".to_string()),
+                fetched_at: Default::default(),
+            }
+            .table_entry()
         )
     }
 
     #[test]
-    fn produces_expected_table_divider() {
-        assert_eq!("|-|-|-|-|-|-|-|-|\n", Crate::table_divider())
+    fn produces_expected_table_line_with_exact_contributors() {
+        assert_eq!(
+            "|example|1.0.0|1.2.0|MIT OR Apache-2.0|100|10000|10|2|01/01/1970|01/01/1970|https://github.com/lhalf/trust-list|1970-01-01T00:00:00Z|\n",
+            Crate {
+                name: "example".to_string(),
+                version: "1.0.0".to_string(),
+                latest_version: Some("1.2.0".to_string()),
+                license: Some("MIT OR Apache-2.0".to_string()),
+                downloads: 100,
+                contributors: Fetched::Present(10000),
+                reverse_dependencies: 10,
+                versions: 2,
+                created_at: Default::default(),
+                updated_at: Default::default(),
+                repository: Fetched::Present("https://github.com/lhalf/trust-list".to_string()),
+                fetched_at: Default::default(),
+            }
+            .table_entry()
+        )
     }
 
     #[test]
-    fn produces_expected_table_line() {
+    fn produces_expected_table_line_when_no_stable_version_is_published() {
         assert_eq!(
-            "|example|100|20|10|2|01/01/1970|01/01/1970|https://github.com/lhalf/trust-list|\n",
+            "|example|0.1.0-beta|||100|20|10|2|01/01/1970|01/01/1970|https://github.com/lhalf/trust-list|1970-01-01T00:00:00Z|\n",
             Crate {
                 name: "example".to_string(),
+                version: "0.1.0-beta".to_string(),
+                latest_version: None,
+                license: None,
                 downloads: 100,
-                contributors: 20,
+                contributors: Fetched::Present(20),
                 reverse_dependencies: 10,
-                versions: vec![0, 1],
+                versions: 2,
                 created_at: Default::default(),
                 updated_at: Default::default(),
-                repository: "https://github.com/lhalf/trust-list".to_string(),
+                repository: Fetched::Present("https://github.com/lhalf/trust-list".to_string()),
+                fetched_at: Default::default(),
             }
             .table_entry()
         )
     }
 
     #[test]
-    fn produces_expected_table_line_when_contributors_over_30() {
+    fn produces_expected_lines_when_contributors_and_repository_are_unknown() {
+        let crate_info = Crate {
+            name: "example".to_string(),
+            version: "1.0.0".to_string(),
+            latest_version: Some("1.2.0".to_string()),
+            license: Some("MIT".to_string()),
+            downloads: 100,
+            contributors: Fetched::Failed("deliberate test error".to_string()),
+            reverse_dependencies: 10,
+            versions: 2,
+            created_at: Default::default(),
+            updated_at: Default::default(),
+            repository: Fetched::Absent,
+            fetched_at: Default::default(),
+        };
+
         assert_eq!(
-            "|example|100|30+|10|2|01/01/1970|01/01/1970|https://github.com/lhalf/trust-list|\n",
+            "|example|1.0.0|1.2.0|MIT|100|?|10|2|01/01/1970|01/01/1970|n/a|1970-01-01T00:00:00Z|\n",
+            crate_info.table_entry()
+        );
+        assert_eq!(
+            "example,1.0.0,1.2.0,MIT,100,,10,2,1970-01-01,1970-01-01,,1970-01-01T00:00:00Z\r\n",
+            crate_info.csv_entry()
+        )
+    }
+
+    #[test]
+    fn produces_expected_csv_line_with_exact_contributors() {
+        assert_eq!(
+            "example,1.0.0,1.2.0,MIT OR Apache-2.0,100,10000,10,2,1970-01-01,1970-01-01,https://github.com/lhalf/trust-list,1970-01-01T00:00:00Z\r\n",
             Crate {
                 name: "example".to_string(),
+                version: "1.0.0".to_string(),
+                latest_version: Some("1.2.0".to_string()),
+                license: Some("MIT OR Apache-2.0".to_string()),
                 downloads: 100,
-                contributors: 10000,
+                contributors: Fetched::Present(10000),
                 reverse_dependencies: 10,
-                versions: vec![0, 1],
+                versions: 2,
                 created_at: Default::default(),
                 updated_at: Default::default(),
-                repository: "https://github.com/lhalf/trust-list".to_string(),
+                repository: Fetched::Present("https://github.com/lhalf/trust-list".to_string()),
+                fetched_at: Default::default(),
             }
-            .table_entry()
+            .csv_entry()
         )
     }
 
     #[test]
-    fn fails_to_reach_crate_url() {
+    fn fails_to_reach_crates_url() {
         let spy = GetRequestSpy::default();
 
         spy.get
@@ -165,19 +308,19 @@ mod tests {
 
         assert_eq!(
             "deliberate test error",
-            get_crate_info(&spy, "invalid").unwrap_err().to_string()
+            get_crates(&spy, &["invalid"]).unwrap_err().to_string()
         )
     }
 
     #[test]
-    fn crate_url_returns_invalid_json() {
+    fn crates_url_returns_invalid_json() {
         let spy = GetRequestSpy::default();
 
         spy.get.returns.set([Ok("invalid JSON".to_string())]);
 
         assert_eq!(
-            "failed to deserialize response from: https://crates.io/api/v1/crates/invalid",
-            get_crate_info(&spy, "invalid").unwrap_err().to_string()
+            "failed to deserialize response from: https://crates.io/api/v1/crates?ids[]=invalid&per_page=50",
+            get_crates(&spy, &["invalid"]).unwrap_err().to_string()
         )
     }
 
@@ -222,29 +365,155 @@ mod tests {
         assert_eq!(32, get_reverse_dependencies(&spy, "valid").unwrap())
     }
 
+    fn fetched_crates(http_client: &impl GetRequest, crate_names: &[&str]) -> Vec<Crate> {
+        get_crates(http_client, crate_names)
+            .unwrap()
+            .into_iter()
+            .map(|(_, crate_info)| crate_info.unwrap())
+            .collect()
+    }
+
     #[test]
-    fn valid_crate_info_and_reverse_dependencies() {
+    fn crates_are_fetched_in_one_request() {
         let spy = GetRequestSpy::default();
 
-        spy.get.returns.set([
-            Ok(include_str!("../tests/data/crate_info.json").to_string()),
-            Ok(r#"{ "dependencies": [], "versions": [], "meta": { "total": 56 } }"#.to_string()),
-        ]);
+        spy.get
+            .returns
+            .set([Ok(include_str!("../tests/data/crates.json").to_string())]);
+
+        let crates = fetched_crates(&spy, &["anyhow", "autospy", "clap"]);
 
         assert_eq!(
             Crate {
                 name: "autospy".to_string(),
+                version: String::new(),
+                latest_version: Some("0.0.8".to_string()),
+                license: None,
                 downloads: 1861,
-                contributors: 0,
-                reverse_dependencies: 56,
-                versions: vec![
-                    1622670, 1603361, 1594229, 1592184, 1588757, 1588227, 1581038, 1564965
-                ],
+                contributors: Fetched::Absent,
+                reverse_dependencies: 0,
+                versions: 8,
                 created_at: DateTime::from_str("2025-05-15T13:17:05.242665Z").unwrap(),
                 updated_at: DateTime::from_str("2025-07-01T12:45:04.998603Z").unwrap(),
-                repository: "https://github.com/lhalf/autospy".to_string(),
+                repository: Fetched::Present("https://github.com/lhalf/autospy".to_string()),
+                fetched_at: Default::default(),
             },
-            get_crate_info(&spy, "autospy").unwrap()
+            crates[1]
+        );
+        assert_eq!(
+            ["anyhow", "autospy", "clap"],
+            crates
+                .iter()
+                .map(|crate_info| crate_info.name.as_str())
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+        assert_eq!(
+            [
+                "https://crates.io/api/v1/crates?ids[]=anyhow&ids[]=autospy&ids[]=clap&per_page=50"
+                    .to_string()
+            ],
+            spy.get.arguments
+        )
+    }
+
+    #[test]
+    fn malformed_record_only_fails_its_own_crate() {
+        let spy = GetRequestSpy::default();
+
+        let mut crates: serde_json::Value =
+            serde_json::from_str(include_str!("../tests/data/crates.json")).unwrap();
+        crates["crates"][0]["downloads"] = "many".into();
+
+        spy.get.returns.set([Ok(crates.to_string())]);
+
+        let crates = get_crates(&spy, &["anyhow", "autospy", "clap"]).unwrap();
+
+        assert_eq!(
+            [("anyhow", false), ("autospy", true), ("clap", true)],
+            crates
+                .iter()
+                .map(|(name, crate_info)| (name.as_str(), crate_info.is_ok()))
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+        assert_eq!(
+            "failed to deserialize anyhow from: https://crates.io/api/v1/crates?ids[]=anyhow&ids[]=autospy&ids[]=clap&per_page=50",
+            crates[0].1.as_ref().unwrap_err().to_string()
+        )
+    }
+
+    #[test]
+    fn crates_not_on_crates_io_are_left_out() {
+        let spy = GetRequestSpy::default();
+
+        spy.get.returns.set([Ok(
+            r#"{ "crates": [], "meta": { "total": 0, "next_page": null, "prev_page": null } }"#
+                .to_string(),
+        )]);
+
+        assert!(fetched_crates(&spy, &["not-published"]).is_empty())
+    }
+
+    #[test]
+    fn crate_names_are_compared_as_crates_io_does() {
+        assert_eq!(canonical_name("Serde_JSON"), canonical_name("serde-json"))
+    }
+
+    fn crate_with_links(repository: &str, homepage: &str, documentation: &str) -> Crate {
+        let spy = GetRequestSpy::default();
+
+        let mut crates: serde_json::Value =
+            serde_json::from_str(include_str!("../tests/data/crates.json")).unwrap();
+        let autospy = &mut crates["crates"][1];
+        autospy["repository"] = serde_json::from_str(repository).unwrap();
+        autospy["homepage"] = serde_json::from_str(homepage).unwrap();
+        autospy["documentation"] = serde_json::from_str(documentation).unwrap();
+
+        spy.get.returns.set([Ok(crates.to_string())]);
+
+        fetched_crates(&spy, &["anyhow", "autospy"]).remove(1)
+    }
+
+    #[test]
+    fn crate_without_repository_links_homepage() {
+        assert_eq!(
+            Fetched::Present("https://autospy.dev".to_string()),
+            crate_with_links(
+                "null",
+                r#""https://autospy.dev""#,
+                r#""https://docs.rs/autospy""#
+            )
+            .repository
+        )
+    }
+
+    #[test]
+    fn crate_without_repository_or_homepage_links_documentation() {
+        assert_eq!(
+            Fetched::Present("https://docs.rs/autospy".to_string()),
+            crate_with_links("null", "null", r#""https://docs.rs/autospy""#).repository
+        )
+    }
+
+    #[test]
+    fn crate_without_any_links_has_absent_repository() {
+        assert_eq!(
+            Fetched::Absent,
+            crate_with_links("null", "null", "null").repository
+        )
+    }
+
+    #[test]
+    fn crate_with_repository_ignores_other_links() {
+        assert_eq!(
+            Fetched::Present("https://github.com/lhalf/autospy".to_string()),
+            crate_with_links(
+                r#""https://github.com/lhalf/autospy""#,
+                r#""https://autospy.dev""#,
+                r#""https://docs.rs/autospy""#
+            )
+            .repository
         )
     }
 }
@@ -0,0 +1,63 @@
+use std::time::Duration;
+
+/// Parses a whole number of seconds, minutes, hours or days, e.g. `30s`, `5m`, `12h` or `7d`
+pub fn parse(duration: &str) -> Result<Duration, String> {
+    let (amount, unit) = duration.split_at(
+        duration
+            .find(|character: char| !character.is_ascii_digit())
+            .unwrap_or(duration.len()),
+    );
+
+    let amount = amount
+        .parse::<u64>()
+        .map_err(|_| format!("invalid duration: {duration}"))?;
+
+    let seconds = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "invalid duration unit: {unit}, expected s, m, h or d"
+            ));
+        }
+    };
+
+    amount
+        .checked_mul(seconds)
+        .map(Duration::from_secs)
+        .ok_or_else(|| format!("duration is too long: {duration}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::duration::parse;
+    use std::time::Duration;
+
+    #[test]
+    fn parses_units() {
+        assert_eq!(Ok(Duration::from_secs(30)), parse("30"));
+        assert_eq!(Ok(Duration::from_secs(30)), parse("30s"));
+        assert_eq!(Ok(Duration::from_secs(5 * 60)), parse("5m"));
+        assert_eq!(Ok(Duration::from_secs(12 * 60 * 60)), parse("12h"));
+        assert_eq!(Ok(Duration::from_secs(7 * 24 * 60 * 60)), parse("7d"))
+    }
+
+    #[test]
+    fn fails_to_parse_invalid_duration() {
+        assert_eq!(Err("invalid duration: h".to_string()), parse("h"));
+        assert_eq!(
+            Err("invalid duration unit: w, expected s, m, h or d".to_string()),
+            parse("2w")
+        )
+    }
+
+    #[test]
+    fn fails_to_parse_overflowing_duration() {
+        assert_eq!(
+            Err("duration is too long: 999999999999999999d".to_string()),
+            parse("999999999999999999d")
+        )
+    }
+}
@@ -0,0 +1,175 @@
+use crate::http_client::{RateLimited, StatusError};
+use crate::output_format::MissingColumn;
+
+/// The subsystem a run failed in, each with its own exit code so wrappers can react to it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Category {
+    /// Dependencies failed the license policy or policy checks, or the policy file is invalid
+    Policy,
+    /// Arguments clap accepted but trust-list could not use, such as an unknown column
+    Usage,
+    /// The list was written without every crate, see the failure summary
+    Incomplete,
+    /// cargo could not be run or could not resolve the dependencies
+    Cargo,
+    /// Every request to crates.io failed, or the HTTP client could not be set up
+    Http,
+    /// The output file or an api response was not in the expected format
+    Deserialize,
+    FileIo,
+}
+
+impl Category {
+    pub fn exit_code(self) -> u8 {
+        match self {
+            Self::Policy => 1,
+            // clap exits with 2 for arguments it rejects
+            Self::Usage => 2,
+            Self::Incomplete => 3,
+            Self::Cargo => 4,
+            Self::Http => 5,
+            Self::Deserialize => 6,
+            Self::FileIo => 7,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Error {
+    pub category: Category,
+    error: anyhow::Error,
+}
+
+impl Error {
+    pub fn new(category: Category, error: impl Into<anyhow::Error>) -> Self {
+        Self {
+            category,
+            error: error.into(),
+        }
+    }
+
+    /// Categorised by the first error in the chain of a known type, otherwise the fallback
+    pub fn classify(error: anyhow::Error, fallback: Category) -> Self {
+        let category = error
+            .chain()
+            .find_map(|cause| {
+                if cause.is::<RateLimited>()
+                    || cause.is::<StatusError>()
+                    || cause.is::<reqwest::Error>()
+                {
+                    Some(Category::Http)
+                } else if cause.is::<MissingColumn>() {
+                    Some(Category::Usage)
+                } else if cause.is::<serde_json::Error>() || cause.is::<toml::de::Error>() {
+                    Some(Category::Deserialize)
+                } else if cause.is::<std::io::Error>() {
+                    Some(Category::FileIo)
+                } else {
+                    None
+                }
+            })
+            .unwrap_or(fallback);
+        Self::new(category, error)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:#}", self.error)
+    }
+}
+
+pub trait Categorise<T> {
+    fn category(self, category: Category) -> Result<T, Error>;
+}
+
+impl<T> Categorise<T> for anyhow::Result<T> {
+    fn category(self, category: Category) -> Result<T, Error> {
+        self.map_err(|error| Error::new(category, error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{Categorise, Category, Error};
+    use crate::output_format::MissingColumn;
+    use anyhow::Context;
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn every_category_has_a_distinct_exit_code() {
+        let categories = [
+            Category::Policy,
+            Category::Usage,
+            Category::Incomplete,
+            Category::Cargo,
+            Category::Http,
+            Category::Deserialize,
+            Category::FileIo,
+        ];
+
+        assert_eq!(
+            categories.len(),
+            categories
+                .map(Category::exit_code)
+                .into_iter()
+                .collect::<BTreeSet<_>>()
+                .len()
+        );
+        assert!(!categories.map(Category::exit_code).contains(&0))
+    }
+
+    #[test]
+    fn errors_are_classified_through_context() {
+        let error = std::fs::read_to_string("/does/not/exist")
+            .context("failed to read output file")
+            .unwrap_err();
+
+        assert_eq!(
+            Category::FileIo,
+            Error::classify(error, Category::Usage).category
+        )
+    }
+
+    #[test]
+    fn deserialize_errors_are_classified() {
+        let error = serde_json::from_str::<Vec<u8>>("not json")
+            .context("output file is not a json array")
+            .unwrap_err();
+
+        assert_eq!(
+            Category::Deserialize,
+            Error::classify(error, Category::FileIo).category
+        )
+    }
+
+    #[test]
+    fn missing_columns_are_usage_errors() {
+        assert_eq!(
+            Category::Usage,
+            Error::classify(MissingColumn("stars".to_string()).into(), Category::FileIo).category
+        )
+    }
+
+    #[test]
+    fn unknown_errors_use_the_fallback() {
+        assert_eq!(
+            Category::FileIo,
+            Error::classify(anyhow::anyhow!("deliberate test error"), Category::FileIo).category
+        )
+    }
+
+    #[test]
+    fn errors_display_their_whole_chain() {
+        let error = Err::<(), _>(anyhow::anyhow!("deliberate test error"))
+            .context("failed to call cargo metadata")
+            .category(Category::Cargo)
+            .unwrap_err();
+
+        assert_eq!(
+            "failed to call cargo metadata: deliberate test error",
+            error.to_string()
+        );
+        assert_eq!(Category::Cargo, error.category)
+    }
+}
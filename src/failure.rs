@@ -0,0 +1,119 @@
+use crate::cargo_metadata::Dependency;
+use std::collections::BTreeMap;
+
+/// What was being fetched for a crate when it failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    CratesIo,
+    ReverseDependencies,
+    GitHub,
+}
+
+impl std::fmt::Display for Stage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::CratesIo => "crates.io info",
+            Self::ReverseDependencies => "reverse dependencies",
+            Self::GitHub => "GitHub contributors",
+        })
+    }
+}
+
+/// A crate missing from the list, or written with an unknown value
+#[derive(Debug, Clone, PartialEq)]
+pub struct Failure {
+    pub name: String,
+    pub version: String,
+    pub stage: Stage,
+    /// The error and every cause, outermost first
+    pub error: String,
+}
+
+impl Failure {
+    pub fn new(dependency: &Dependency, stage: Stage, error: impl Into<String>) -> Self {
+        Self {
+            name: dependency.name.clone(),
+            version: dependency.version.clone(),
+            stage,
+            error: error.into(),
+        }
+    }
+}
+
+/// Every failure grouped by stage, in the order the stages are fetched, empty if there were none
+pub fn summary(failures: &[Failure]) -> String {
+    if failures.is_empty() {
+        return String::new();
+    }
+
+    let mut stages = BTreeMap::<Stage, Vec<&Failure>>::new();
+    for failure in failures {
+        stages.entry(failure.stage).or_default().push(failure);
+    }
+
+    let mut summary = format!("{} crates could not be fully fetched\n", failures.len());
+    for (stage, failures) in stages {
+        summary.push_str(&format!("\n{stage} ({}):\n", failures.len()));
+        for failure in failures {
+            summary.push_str(&format!(
+                "  {} {}: {}\n",
+                failure.name, failure.version, failure.error
+            ));
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::failure::{Failure, Stage, summary};
+
+    fn failure(name: &str, stage: Stage, error: &str) -> Failure {
+        Failure {
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            stage,
+            error: error.to_string(),
+        }
+    }
+
+    #[test]
+    fn no_failures_is_an_empty_summary() {
+        assert_eq!("", summary(&[]))
+    }
+
+    #[test]
+    fn failures_are_grouped_by_stage_in_fetch_order() {
+        assert_eq!(
+            [
+                "3 crates could not be fully fetched",
+                "",
+                "crates.io info (1):",
+                "  anyhow 1.0.0: invalid response from: https://crates.io/api/v1/crates/anyhow, 404 Not Found",
+                "",
+                "GitHub contributors (2):",
+                "  autospy 1.0.0: rate limited by: https://api.github.com/repos/lhalf/autospy/contributors",
+                "  clap 1.0.0: failed to send request to: https://api.github.com/repos/clap-rs/clap/contributors: timed out",
+                "",
+            ]
+            .join("\n"),
+            summary(&[
+                failure(
+                    "autospy",
+                    Stage::GitHub,
+                    "rate limited by: https://api.github.com/repos/lhalf/autospy/contributors"
+                ),
+                failure(
+                    "anyhow",
+                    Stage::CratesIo,
+                    "invalid response from: https://crates.io/api/v1/crates/anyhow, 404 Not Found"
+                ),
+                failure(
+                    "clap",
+                    Stage::GitHub,
+                    "failed to send request to: https://api.github.com/repos/clap-rs/clap/contributors: timed out"
+                ),
+            ])
+        )
+    }
+}
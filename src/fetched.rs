@@ -0,0 +1,94 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt::Display;
+
+/// A value from an api that may not be known, so it is never mistaken for a real zero
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Fetched<T> {
+    Present(T),
+    /// There is nothing to fetch, such as the contributors of a repository not on GitHub
+    #[default]
+    Absent,
+    /// The value exists but could not be fetched, with the reason why
+    Failed(String),
+}
+
+impl<T> Fetched<T> {
+    pub fn present(&self) -> Option<&T> {
+        match self {
+            Self::Present(value) => Some(value),
+            Self::Absent | Self::Failed(_) => None,
+        }
+    }
+}
+
+impl<T: Display> Fetched<T> {
+    pub fn table_cell(&self) -> String {
+        match self {
+            Self::Present(value) => value.to_string(),
+            Self::Absent => "n/a".to_string(),
+            Self::Failed(_) => "?".to_string(),
+        }
+    }
+
+    pub fn csv_field(&self) -> String {
+        self.present().map(T::to_string).unwrap_or_default()
+    }
+}
+
+impl<T: Serialize> Serialize for Fetched<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.present().serialize(serializer)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Fetched<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Option::deserialize(deserializer)?.map_or(Self::Absent, Self::Present))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fetched::Fetched;
+
+    #[test]
+    fn unknown_values_are_marked_in_table_cells() {
+        assert_eq!("0", Fetched::Present(0).table_cell());
+        assert_eq!("n/a", Fetched::<u16>::Absent.table_cell());
+        assert_eq!(
+            "?",
+            Fetched::<u16>::Failed("timed out".to_string()).table_cell()
+        )
+    }
+
+    #[test]
+    fn unknown_values_are_empty_csv_fields() {
+        assert_eq!("0", Fetched::Present(0).csv_field());
+        assert_eq!("", Fetched::<u16>::Absent.csv_field());
+        assert_eq!(
+            "",
+            Fetched::<u16>::Failed("timed out".to_string()).csv_field()
+        )
+    }
+
+    #[test]
+    fn unknown_values_are_serialized_as_null() {
+        assert_eq!(
+            "[0,null,null]",
+            serde_json::to_string(&[
+                Fetched::Present(0),
+                Fetched::Absent,
+                Fetched::Failed("timed out".to_string())
+            ])
+            .unwrap()
+        )
+    }
+
+    #[test]
+    fn null_is_deserialized_as_absent() {
+        assert_eq!(
+            [Fetched::Present(1), Fetched::Absent],
+            serde_json::from_str::<[Fetched<u16>; 2]>("[1,null]").unwrap()
+        )
+    }
+}
@@ -1,54 +1,131 @@
-use crate::crates_io::Crate;
+use crate::output_format::OutputFormat;
 use anyhow::Context;
 use std::io::Write;
+use std::ops::Range;
 use std::path::PathBuf;
 
+const START_MARKER: &str = "<!-- trust-list:start -->";
+const END_MARKER: &str = "<!-- trust-list:end -->";
+
 pub struct OutputFile {
     pub path: PathBuf,
+    /// Only the content between the markers is read and written, the rest of the file is left intact
+    embedded: bool,
 }
 
 impl OutputFile {
-    pub fn new(path: PathBuf, recreate: bool) -> Result<Self, anyhow::Error> {
-        let file = Self { path };
+    pub fn new(
+        path: PathBuf,
+        output_format: OutputFormat,
+        columns: &[String],
+    ) -> Result<Self, anyhow::Error> {
+        Self::open(
+            Self {
+                path,
+                embedded: false,
+            },
+            output_format,
+            columns,
+        )
+    }
 
-        if recreate {
-            file.remove();
-        }
+    pub fn embedded(path: PathBuf, columns: &[String]) -> Result<Self, anyhow::Error> {
+        Self::open(
+            Self {
+                path,
+                embedded: true,
+            },
+            OutputFormat::Markdown,
+            columns,
+        )
+    }
 
+    fn open(file: Self, output_format: OutputFormat, columns: &[String]) -> anyhow::Result<Self> {
         if !file.exists() {
             file.create()?;
-            file.append(Crate::table_heading().as_bytes())?;
-            file.append(Crate::table_divider().as_bytes())?;
+            file.append(output_format.heading(columns).as_bytes())?;
         }
 
         Ok(file)
     }
+
+    fn read_document(&self) -> anyhow::Result<String> {
+        std::fs::read_to_string(&self.path).context("failed to read output file")
+    }
+
+    fn replace_embedded(&self, replace: impl FnOnce(&str) -> String) -> anyhow::Result<()> {
+        let mut document = self.read_document()?;
+        let region = embedded_region(&document)?;
+        let replacement = replace(&document[region.clone()]);
+        document.replace_range(region, &replacement);
+        std::fs::write(&self.path, document).context("unable to write to output file")
+    }
+}
+
+/// The lines between the start and end marker lines, markers in text or code blocks are left as they are
+fn embedded_region(document: &str) -> anyhow::Result<Range<usize>> {
+    let mut in_code_block = false;
+    let mut marker_lines = document
+        .split_inclusive('\n')
+        .scan(0, |start, line| {
+            let span = *start..*start + line.len();
+            *start = span.end;
+            Some((span, line.trim()))
+        })
+        .filter(|(_, line)| {
+            if line.starts_with("```") || line.starts_with("~~~") {
+                in_code_block = !in_code_block;
+            }
+            !in_code_block && [START_MARKER, END_MARKER].contains(line)
+        });
+
+    let start = marker_lines
+        .find(|(_, line)| *line == START_MARKER)
+        .with_context(|| format!("output file does not contain {START_MARKER}"))?
+        .0
+        .end;
+    let end = marker_lines
+        .find(|(_, line)| *line == END_MARKER)
+        .with_context(|| format!("output file does not contain {END_MARKER} after {START_MARKER}"))?
+        .0
+        .start;
+    Ok(start..end)
 }
 
 #[cfg_attr(test, autospy::autospy)]
 pub trait FileIO {
     fn exists(&self) -> bool;
-    fn remove(&self);
     fn create(&self) -> anyhow::Result<()>;
     fn append(&self, contents: &[u8]) -> anyhow::Result<()>;
+    fn write(&self, contents: &[u8]) -> anyhow::Result<()>;
     fn read_to_string(&self) -> anyhow::Result<String>;
 }
 
 impl FileIO for OutputFile {
     fn exists(&self) -> bool {
-        self.path.exists()
-    }
-
-    fn remove(&self) {
-        let _ = std::fs::remove_file(&self.path);
+        match self.embedded {
+            true => self
+                .read_to_string()
+                .is_ok_and(|contents| !contents.trim().is_empty()),
+            false => self.path.exists(),
+        }
     }
 
     fn create(&self) -> anyhow::Result<()> {
-        std::fs::File::create(&self.path).context("failed to create output file")?;
-        Ok(())
+        match self.embedded {
+            true => self.read_to_string().map(|_| ()),
+            false => {
+                std::fs::File::create(&self.path).context("failed to create output file")?;
+                Ok(())
+            }
+        }
     }
 
     fn append(&self, contents: &[u8]) -> anyhow::Result<()> {
+        if self.embedded {
+            let contents = String::from_utf8_lossy(contents);
+            return self.replace_embedded(|existing| [existing, &contents].join(""));
+        }
         std::fs::OpenOptions::new()
             .append(true)
             .open(&self.path)
@@ -57,7 +134,137 @@ impl FileIO for OutputFile {
             .context("unable to write to output file")
     }
 
+    fn write(&self, contents: &[u8]) -> anyhow::Result<()> {
+        if self.embedded {
+            let contents = String::from_utf8_lossy(contents);
+            return self.replace_embedded(|_| contents.to_string());
+        }
+        std::fs::write(&self.path, contents).context("unable to write to output file")
+    }
+
     fn read_to_string(&self) -> anyhow::Result<String> {
-        std::fs::read_to_string(&self.path).context("failed to read output file")
+        let document = self.read_document()?;
+        match self.embedded {
+            true => Ok(document[embedded_region(&document)?].to_string()),
+            false => Ok(document),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::file_io::{FileIO, OutputFile, embedded_region};
+    use crate::output_format::{OutputFormat, select_columns};
+
+    const DOCUMENT: &str = "# Dependencies\n\nIntro.\n\n<!-- trust-list:start -->\n|name|\n|-|\n<!-- trust-list:end -->\n\nOutro.\n";
+
+    fn temp_file(name: &str, contents: &str) -> std::path::PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("trust-list-{name}-{}.md", std::process::id()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn embedded_region_is_between_marker_lines() {
+        let region = embedded_region(DOCUMENT).unwrap();
+
+        assert_eq!("|name|\n|-|\n", &DOCUMENT[region])
+    }
+
+    #[test]
+    fn embedded_region_of_adjacent_markers_is_empty() {
+        let document = "<!-- trust-list:start -->\n<!-- trust-list:end -->\n";
+
+        assert!(embedded_region(document).unwrap().is_empty())
+    }
+
+    #[test]
+    fn markers_in_text_and_code_blocks_are_not_the_region() {
+        let document = [
+            "Writes between <!-- trust-list:start --> and <!-- trust-list:end -->.\n",
+            "```markdown\n<!-- trust-list:start -->\n<!-- trust-list:end -->\n```\n",
+            "  <!-- trust-list:start -->  \n|name|\n|-|\n<!-- trust-list:end -->\n",
+        ]
+        .join("");
+
+        assert_eq!(
+            "|name|\n|-|\n",
+            &document[embedded_region(&document).unwrap()]
+        )
+    }
+
+    #[test]
+    fn missing_start_marker() {
+        assert_eq!(
+            "output file does not contain <!-- trust-list:start -->",
+            embedded_region("<!-- trust-list:end -->")
+                .unwrap_err()
+                .to_string()
+        )
+    }
+
+    #[test]
+    fn missing_end_marker() {
+        assert_eq!(
+            "output file does not contain <!-- trust-list:end --> after <!-- trust-list:start -->",
+            embedded_region("<!-- trust-list:end -->\n<!-- trust-list:start -->\n")
+                .unwrap_err()
+                .to_string()
+        )
+    }
+
+    #[test]
+    fn embedded_file_only_changes_content_between_markers() {
+        let path = temp_file("embedded", DOCUMENT);
+        let file = OutputFile::embedded(path.clone(), &select_columns(None).unwrap()).unwrap();
+
+        file.append(b"|anyhow|\n").unwrap();
+        assert_eq!("|name|\n|-|\n|anyhow|\n", file.read_to_string().unwrap());
+
+        file.write(b"|name|\n|-|\n|syn|\n").unwrap();
+        assert_eq!(
+            "# Dependencies\n\nIntro.\n\n<!-- trust-list:start -->\n|name|\n|-|\n|syn|\n<!-- trust-list:end -->\n\nOutro.\n",
+            std::fs::read_to_string(&path).unwrap()
+        );
+
+        std::fs::remove_file(path).unwrap()
+    }
+
+    #[test]
+    fn empty_embedded_file_gets_heading_between_markers() {
+        let path = temp_file(
+            "embedded-empty",
+            "Intro.\n<!-- trust-list:start -->\n<!-- trust-list:end -->\n",
+        );
+
+        OutputFile::embedded(path.clone(), &select_columns(None).unwrap()).unwrap();
+
+        assert_eq!(
+            [
+                "Intro.\n<!-- trust-list:start -->\n",
+                &OutputFormat::Markdown.heading(&select_columns(None).unwrap()),
+                "<!-- trust-list:end -->\n"
+            ]
+            .join(""),
+            std::fs::read_to_string(&path).unwrap()
+        );
+
+        std::fs::remove_file(path).unwrap()
+    }
+
+    #[test]
+    fn embedding_in_file_without_markers_fails() {
+        let path = temp_file("embedded-no-markers", "# Dependencies\n");
+
+        assert_eq!(
+            "output file does not contain <!-- trust-list:start -->",
+            OutputFile::embedded(path.clone(), &select_columns(None).unwrap())
+                .err()
+                .unwrap()
+                .to_string()
+        );
+
+        std::fs::remove_file(path).unwrap()
     }
 }
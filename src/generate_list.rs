@@ -1,63 +1,410 @@
-use crate::crates_io::get_crate_info;
+use crate::cargo_metadata::Dependency;
+use crate::crates_io::{self, Crate, canonical_name, get_crates, get_reverse_dependencies};
+use crate::failure::{Failure, Stage};
+use crate::fetched::Fetched;
 use crate::file_io::FileIO;
 use crate::github::get_contributor_count;
-use crate::http_client::GetRequest;
+use crate::http_client::{GetRequest, RateLimited, StatusError};
+use crate::license::LicensePolicy;
+use crate::offline_db::Offline;
+use crate::output_format::{self, OutputFormat};
 use crate::progress_bar::ProgressBar;
+use crate::scheduler;
 use anyhow::Error;
-use itertools::Itertools;
-use std::collections::BTreeSet;
+use chrono::{DateTime, TimeDelta, Utc};
+use std::collections::{BTreeSet, HashMap};
+use std::time::Duration;
+
+pub struct Options {
+    pub output_format: OutputFormat,
+    /// The generated columns in order, existing tables keep their heading until recreated
+    pub columns: Vec<String>,
+    /// Empty the output file first, columns users added keep their cells
+    pub recreate: bool,
+    /// Fetch crates already in the output file, only missing and stale crates are written
+    pub fetch_existing: bool,
+    /// Rewrite crates fetched longer ago than this
+    pub refresh_older_than: Option<Duration>,
+    /// Remove crates from the output file that are no longer dependencies
+    pub prune: bool,
+    /// Sort every crate in the output file by this column once written
+    pub sort_by: Option<String>,
+    pub descending: bool,
+    /// Fetch the reverse dependencies of each crate even when the output file has no column for them,
+    /// they are a request per crate unlike the rest of its info
+    pub reverse_dependencies: bool,
+    pub license_policy: LicensePolicy,
+    pub jobs: usize,
+    pub now: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub struct GeneratedList {
+    /// Every crate fetched, including those already in the output file when fetching existing
+    pub crates: Vec<Crate>,
+    /// Crates left out of the list, or written with an unknown value
+    pub failures: Vec<Failure>,
+    /// Every dependency failing the license policy, whether or not it was fetched
+    pub license_violations: Vec<String>,
+    /// The name and version of each crate pruned from the output file
+    pub removed: Vec<(String, String)>,
+}
 
 pub fn generate_list(
-    crate_names: BTreeSet<String>,
+    dependencies: Vec<Dependency>,
     output_file: &impl FileIO,
-    http_client: &impl GetRequest,
+    http_client: &(impl GetRequest + Sync),
     progress_bar: &mut impl ProgressBar,
-) -> Result<(), Error> {
-    let existing_names = parse_existing_crate_names(&output_file.read_to_string()?);
-    let missing_names = crate_names.difference(&existing_names);
-    progress_bar.set_total(missing_names.try_len().unwrap_or(0) as u64);
-
-    for crate_name in missing_names {
-        progress_bar.set_message(&format!("{crate_name} "));
-        match get_crate_info(http_client, crate_name) {
-            Ok(mut crate_info) => {
-                crate_info.contributors =
-                    get_contributor_count(http_client, &crate_info.repository).unwrap_or(0);
-
-                output_file.append(crate_info.table_entry().as_bytes())?;
+    options: &Options,
+) -> Result<GeneratedList, Error> {
+    let license_violations = options.license_policy.violations(&dependencies);
+    // path, git and other registry dependencies aren't on crates.io, or are a different crate there
+    let dependencies = dependencies
+        .into_iter()
+        .filter(|dependency| dependency.source.is_crates_io())
+        .collect::<Vec<_>>();
+    let mut contents = output_file.read_to_string()?;
+    let mut removed = Vec::new();
+    let existing_crates = match options.recreate {
+        // an unreadable output file can still be recreated
+        true => options
+            .output_format
+            .existing_crates(&contents)
+            .unwrap_or_default(),
+        false => options.output_format.existing_crates(&contents)?,
+    };
+    // a recreated file is only written once every crate is fetched, so a failed run leaves it as it was
+    let writer = Deferred {
+        output_file,
+        deferred: options.recreate,
+    };
+    if options.recreate {
+        contents = options.output_format.recreate(&contents, &options.columns);
+    }
+    // an unknown column fails before anything is fetched or written, checked against the heading
+    // the table will have
+    if let Some(column) = &options.sort_by {
+        options
+            .output_format
+            .check_column(&contents, &options.columns, column)?;
+    }
+    if options.prune && !options.recreate {
+        removed =
+            options
+                .output_format
+                .remove_entries(output_file, &mut contents, |name, version| {
+                    dependencies.iter().any(|dependency| {
+                        output_format::is_crate(
+                            name,
+                            version,
+                            &dependency.name,
+                            &dependency.version,
+                        )
+                    })
+                })?;
+    }
+    // rows are laid out by the existing heading, which may have the column when it isn't selected
+    let reverse_dependencies = options.reverse_dependencies
+        || options
+            .output_format
+            .check_column(&contents, &options.columns, "reverse_dependencies")
+            .is_ok();
+    let existing = |dependency: &Dependency| {
+        existing_crates
+            .get(&(dependency.name.clone(), dependency.version.clone()))
+            // tables written before versions were recorded have a row per crate
+            .or_else(|| existing_crates.get(&(dependency.name.clone(), String::new())))
+    };
+    let is_fresh = |dependency: &Dependency| {
+        match existing(dependency).filter(|_| !options.recreate) {
+            None => false,
+            Some(record) => match options.refresh_older_than {
+                None => true,
+                // rows written before fetched_at was recorded are always stale
+                Some(refresh_older_than) => {
+                    output_format::fetched_at(record).is_some_and(|fetched_at| {
+                        options.now - fetched_at
+                            <= TimeDelta::from_std(refresh_older_than).unwrap_or(TimeDelta::MAX)
+                    })
+                }
+            },
+        }
+    };
+    let required_dependencies = dependencies
+        .iter()
+        .filter(|dependency| options.fetch_existing || !is_fresh(dependency))
+        .collect::<Vec<_>>();
+    progress_bar.set_total(required_dependencies.len() as u64);
+
+    let crates_io = fetch_crates_io(http_client, &required_dependencies)?;
+
+    let mut crates = Vec::new();
+    let mut failures = Vec::new();
+
+    scheduler::run_in_order(
+        &required_dependencies,
+        options.jobs,
+        |dependency| {
+            fetch_crate(
+                http_client,
+                &crates_io,
+                dependency,
+                reverse_dependencies,
+                options,
+            )
+        },
+        |dependency, result| {
+            progress_bar.set_message(&format!("{} ", dependency.name));
+            match result {
+                Ok(crate_info) => {
+                    if let Fetched::Failed(reason) = &crate_info.contributors {
+                        failures.push(Failure::new(dependency, Stage::GitHub, reason));
+                    }
+                    if !is_fresh(dependency) {
+                        options.output_format.write_entry(
+                            &writer,
+                            &mut contents,
+                            &crate_info,
+                            existing(dependency),
+                            &options.columns,
+                        )?;
+                    }
+                    crates.push(crate_info);
+                }
+                Err(failure) => {
+                    // the row of a crate that could not be fetched again is kept with the cells users added
+                    if let Some(record) = existing(dependency).filter(|_| options.recreate) {
+                        options.output_format.restore_entry(
+                            &writer,
+                            &mut contents,
+                            (&dependency.name, &dependency.version),
+                            record,
+                        )?;
+                    }
+                    failures.push(failure)
+                }
             }
+            progress_bar.increment();
+            Ok(())
+        },
+    )?;
+
+    if let Some(column) = &options.sort_by {
+        options
+            .output_format
+            .sort_entries(&writer, &mut contents, column, options.descending)?;
+    }
+    if options.recreate {
+        output_file.write(contents.as_bytes())?;
+    }
+
+    Ok(GeneratedList {
+        crates,
+        failures,
+        license_violations,
+        removed,
+    })
+}
+
+/// Leaves writes to the output file until the list is complete when deferred, `contents` tracks them meanwhile
+struct Deferred<'a, F> {
+    output_file: &'a F,
+    deferred: bool,
+}
+
+impl<F: FileIO> FileIO for Deferred<'_, F> {
+    fn exists(&self) -> bool {
+        self.output_file.exists()
+    }
+
+    fn create(&self) -> anyhow::Result<()> {
+        self.output_file.create()
+    }
+
+    fn append(&self, contents: &[u8]) -> anyhow::Result<()> {
+        match self.deferred {
+            true => Ok(()),
+            false => self.output_file.append(contents),
+        }
+    }
+
+    fn write(&self, contents: &[u8]) -> anyhow::Result<()> {
+        match self.deferred {
+            true => Ok(()),
+            false => self.output_file.write(contents),
+        }
+    }
+
+    fn read_to_string(&self) -> anyhow::Result<String> {
+        self.output_file.read_to_string()
+    }
+}
+
+/// The crates.io info of every dependency by canonical name, many crates to a request,
+/// fails when crates.io could not be reached at all rather than failing every crate
+fn fetch_crates_io(
+    http_client: &impl GetRequest,
+    dependencies: &[&Dependency],
+) -> Result<HashMap<String, Result<Crate, String>>, Error> {
+    let crate_names = dependencies
+        .iter()
+        .map(|dependency| dependency.name.as_str())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    let mut crates = HashMap::new();
+    let mut errors = Vec::new();
+    for batch in crate_names.chunks(crates_io::BATCH_SIZE) {
+        match get_crates(http_client, batch) {
+            Ok(fetched) => crates.extend(fetched.into_iter().map(|(crate_name, crate_info)| {
+                (
+                    canonical_name(&crate_name),
+                    crate_info.map_err(|error| format!("{error:#}")),
+                )
+            })),
+            // every crate in the batch fails with the request
             Err(error) => {
-                println!("failed to get info for {crate_name}: {error}");
+                crates.extend(
+                    batch
+                        .iter()
+                        .map(|crate_name| (canonical_name(crate_name), Err(format!("{error:#}")))),
+                );
+                errors.push(error);
             }
         }
-        progress_bar.increment();
     }
 
-    Ok(())
+    // a dead network or crates.io outage fails the run rather than each crate
+    if !errors.is_empty()
+        && errors.len() == crate_names.len().div_ceil(crates_io::BATCH_SIZE)
+        && errors.iter().all(is_unreachable)
+    {
+        return Err(errors
+            .swap_remove(0)
+            .context("every request to crates.io failed"));
+    }
+    Ok(crates)
+}
+
+/// The request never got a response, or got an error status instead of the crates
+fn is_unreachable(error: &Error) -> bool {
+    error
+        .chain()
+        .any(|cause| cause.is::<reqwest::Error>() || cause.is::<StatusError>())
 }
 
-fn parse_existing_crate_names(contents: &str) -> BTreeSet<String> {
-    contents
-        .lines()
-        .skip(2)
-        .filter_map(|line| line.split('|').nth(1))
-        .map(str::trim)
-        .filter(|s| !s.is_empty())
-        .map(String::from)
-        .collect()
+fn fetch_crate(
+    http_client: &impl GetRequest,
+    crates_io: &HashMap<String, Result<Crate, String>>,
+    dependency: &Dependency,
+    reverse_dependencies: bool,
+    options: &Options,
+) -> Result<Crate, Failure> {
+    let failure = |stage, error: Error| Failure::new(dependency, stage, format!("{error:#}"));
+    let mut crate_info = match crates_io.get(&canonical_name(&dependency.name)) {
+        Some(Ok(crate_info)) => crate_info.clone(),
+        Some(Err(error)) => return Err(Failure::new(dependency, Stage::CratesIo, error)),
+        None => {
+            return Err(Failure::new(
+                dependency,
+                Stage::CratesIo,
+                format!("{} was not found on crates.io", dependency.name),
+            ));
+        }
+    };
+    // crates.io treats - and _ the same, set crate name to the package name
+    // so when appending we don't get the name again
+    crate_info.name = dependency.name.clone();
+    crate_info.version = dependency.version.clone();
+    crate_info.license = dependency.license.clone();
+    if reverse_dependencies {
+        crate_info.reverse_dependencies =
+            get_reverse_dependencies(http_client, &dependency.name)
+                .map_err(|error| failure(Stage::ReverseDependencies, error))?;
+    }
+    crate_info.contributors = match &crate_info.repository {
+        Fetched::Present(repository) => match get_contributor_count(http_client, repository) {
+            Ok(contributors) => contributors,
+            // rate limited requests fail the crate so they can be waited for or retried
+            Err(error) if error.is::<RateLimited>() => return Err(failure(Stage::GitHub, error)),
+            Err(error) if error.is::<Offline>() => Fetched::Absent,
+            Err(error) => Fetched::Failed(format!("{error:#}")),
+        },
+        Fetched::Absent | Fetched::Failed(_) => Fetched::Absent,
+    };
+    crate_info.fetched_at = options.now;
+    Ok(crate_info)
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::cargo_metadata::{Dependency, DependencyKind, Source};
+    use crate::crates_io::Crate;
+    use crate::error::{Category, Error};
+    use crate::failure::{Failure, Stage};
+    use crate::fetched::Fetched;
     use crate::file_io::FileIOSpy;
-    use crate::generate_list::generate_list;
-    use crate::http_client::GetRequestSpy;
+    use crate::generate_list::{Options, generate_list};
+    use crate::http_client::{GetRequestSpy, RateLimited, Response, StatusError};
+    use crate::license::LicensePolicy;
+    use crate::offline_db::Offline;
+    use crate::output_format::{OutputFormat, select_columns};
     use crate::progress_bar::ProgressBarSpy;
+    use chrono::DateTime;
+    use reqwest::StatusCode;
     use std::collections::BTreeSet;
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    fn options(output_format: OutputFormat) -> Options {
+        Options {
+            output_format,
+            columns: select_columns(None).unwrap(),
+            recreate: false,
+            fetch_existing: false,
+            refresh_older_than: None,
+            prune: false,
+            sort_by: None,
+            descending: false,
+            reverse_dependencies: true,
+            license_policy: LicensePolicy::default(),
+            jobs: 1,
+            now: DateTime::from_str("2025-10-01T12:00:00Z").unwrap(),
+        }
+    }
+
+    fn contributors_response() -> Response {
+        Response {
+            body: r#"[1,2,3,4,5]"#.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn failure(stage: Stage, error: &str) -> Failure {
+        Failure {
+            name: "autospy".to_string(),
+            version: "0.0.7".to_string(),
+            stage,
+            error: error.to_string(),
+        }
+    }
+
+    fn dependency(name: &str, version: &str) -> Dependency {
+        Dependency {
+            name: name.to_string(),
+            version: version.to_string(),
+            license: Some("MIT".to_string()),
+            source: Source::Registry(
+                "registry+https://github.com/rust-lang/crates.io-index".to_string(),
+            ),
+            kinds: BTreeSet::from([DependencyKind::Normal]),
+        }
+    }
 
     #[test]
     fn output_file_exists_but_cant_be_read() {
-        let crates = BTreeSet::new();
+        let crates = Vec::new();
         let file_io_spy = FileIOSpy::default();
         let http_client_spy = GetRequestSpy::default();
         let mut progress_bar_spy = ProgressBarSpy::default();
@@ -73,7 +420,8 @@ mod tests {
                 crates,
                 &file_io_spy,
                 &http_client_spy,
-                &mut progress_bar_spy
+                &mut progress_bar_spy,
+                &options(OutputFormat::Markdown),
             )
             .unwrap_err()
             .to_string()
@@ -82,7 +430,7 @@ mod tests {
 
     #[test]
     fn no_crates_are_required() {
-        let crates = BTreeSet::new();
+        let crates = Vec::new();
         let file_io_spy = FileIOSpy::default();
         let http_client_spy = GetRequestSpy::default();
         let mut progress_bar_spy = ProgressBarSpy::default();
@@ -96,15 +444,141 @@ mod tests {
                 crates,
                 &file_io_spy,
                 &http_client_spy,
-                &mut progress_bar_spy
+                &mut progress_bar_spy,
+                &options(OutputFormat::Markdown),
             )
             .is_ok()
         )
     }
 
+    #[test]
+    fn sorting_by_unknown_column_fails_before_fetching() {
+        let crates = vec![dependency("autospy", "0.0.7")];
+        let file_io_spy = FileIOSpy::default();
+        let http_client_spy = GetRequestSpy::default();
+        let mut progress_bar_spy = ProgressBarSpy::default();
+
+        file_io_spy
+            .read_to_string
+            .returns
+            .set([Ok(OutputFormat::Markdown.heading(Crate::columns()))]);
+
+        assert_eq!(
+            "output file has no stars column",
+            generate_list(
+                crates,
+                &file_io_spy,
+                &http_client_spy,
+                &mut progress_bar_spy,
+                &Options {
+                    sort_by: Some("stars".to_string()),
+                    ..options(OutputFormat::Markdown)
+                },
+            )
+            .unwrap_err()
+            .to_string()
+        );
+        assert!(http_client_spy.get.arguments.take().is_empty())
+    }
+
+    #[test]
+    fn sorting_recreated_table_by_column_it_drops_fails_before_writing() {
+        let crates = vec![dependency("autospy", "0.0.7")];
+        let file_io_spy = FileIOSpy::default();
+        let http_client_spy = GetRequestSpy::default();
+        let mut progress_bar_spy = ProgressBarSpy::default();
+
+        file_io_spy
+            .read_to_string
+            .returns
+            .set([Ok(OutputFormat::Markdown.heading(Crate::columns()))]);
+
+        assert_eq!(
+            "output file has no downloads column",
+            generate_list(
+                crates,
+                &file_io_spy.clone(),
+                &http_client_spy,
+                &mut progress_bar_spy,
+                &Options {
+                    recreate: true,
+                    columns: select_columns(Some(vec!["name".to_string(), "version".to_string()]))
+                        .unwrap(),
+                    sort_by: Some("downloads".to_string()),
+                    ..options(OutputFormat::Markdown)
+                },
+            )
+            .unwrap_err()
+            .to_string()
+        );
+        assert!(http_client_spy.get.arguments.take().is_empty());
+        assert!(file_io_spy.write.arguments.take().is_empty())
+    }
+
+    #[test]
+    fn sorting_by_unknown_column_fails_before_pruning() {
+        let crates = vec![dependency("autospy", "0.0.7")];
+        let file_io_spy = FileIOSpy::default();
+        let http_client_spy = GetRequestSpy::default();
+        let mut progress_bar_spy = ProgressBarSpy::default();
+
+        file_io_spy.read_to_string.returns.set([Ok(
+            "|name|version|\n|-|-|\n|autospy|0.0.7|\n|anyhow|1.0.0|\n".to_string(),
+        )]);
+
+        assert!(
+            generate_list(
+                crates,
+                &file_io_spy.clone(),
+                &http_client_spy,
+                &mut progress_bar_spy,
+                &Options {
+                    prune: true,
+                    sort_by: Some("stars".to_string()),
+                    ..options(OutputFormat::Markdown)
+                },
+            )
+            .is_err()
+        );
+        assert!(file_io_spy.write.arguments.take().is_empty())
+    }
+
+    #[test]
+    fn dependencies_not_from_crates_io_are_skipped() {
+        let crates = vec![
+            Dependency {
+                source: Source::Path,
+                ..dependency("local-utils", "0.2.0")
+            },
+            Dependency {
+                source: Source::Git("git+https://github.com/a8m/pb?rev=abc123#abc123".to_string()),
+                ..dependency("pbr", "1.1.1")
+            },
+        ];
+        let file_io_spy = FileIOSpy::default();
+        let http_client_spy = GetRequestSpy::default();
+        let mut progress_bar_spy = ProgressBarSpy::default();
+
+        file_io_spy.read_to_string.returns.set([Ok(String::new())]);
+
+        progress_bar_spy.set_total.returns.set([()]);
+
+        let generated = generate_list(
+            crates,
+            &file_io_spy,
+            &http_client_spy,
+            &mut progress_bar_spy,
+            &options(OutputFormat::Markdown),
+        )
+        .unwrap();
+
+        assert!(generated.crates.is_empty() && generated.failures.is_empty());
+        assert_eq!([0], progress_bar_spy.set_total.arguments)
+    }
+
     #[test]
     fn single_crate_required_get_crate_info_fails() {
-        let crates = BTreeSet::from(["autospy".to_string()]);
+        let crates = vec![dependency("autospy", "0.0.7")];
         let file_io_spy = FileIOSpy::default();
         let http_client_spy = GetRequestSpy::default();
         let mut progress_bar_spy = ProgressBarSpy::default();
@@ -121,20 +595,61 @@ mod tests {
 
         progress_bar_spy.increment.returns.set([()]);
 
-        assert!(
+        assert_eq!(
+            [failure(Stage::CratesIo, "deliberate test error")],
             generate_list(
                 crates,
                 &file_io_spy,
                 &http_client_spy,
-                &mut progress_bar_spy
+                &mut progress_bar_spy,
+                &options(OutputFormat::Markdown),
             )
-            .is_ok()
+            .unwrap()
+            .failures
+            .as_slice()
+        )
+    }
+
+    #[test]
+    fn crates_io_unreachable_fails_the_list() {
+        let crates = vec![dependency("autospy", "0.0.7")];
+        let file_io_spy = FileIOSpy::default();
+        let http_client_spy = GetRequestSpy::default();
+        let mut progress_bar_spy = ProgressBarSpy::default();
+
+        file_io_spy.read_to_string.returns.set([Ok(String::new())]);
+
+        progress_bar_spy.set_total.returns.set([()]);
+
+        http_client_spy.get.returns.set([Err(StatusError {
+            url: "https://crates.io/api/v1/crates".to_string(),
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            retry_after: None,
+        }
+        .into())]);
+
+        let error = generate_list(
+            crates,
+            &file_io_spy,
+            &http_client_spy,
+            &mut progress_bar_spy,
+            &options(OutputFormat::Markdown),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            "every request to crates.io failed: invalid response from: https://crates.io/api/v1/crates, 503 Service Unavailable",
+            format!("{error:#}")
+        );
+        assert_eq!(
+            Category::Http,
+            Error::classify(error, Category::FileIo).category
         )
     }
 
     #[test]
     fn single_crate_required_get_reverse_dependencies_fails() {
-        let crates = BTreeSet::from(["autospy".to_string()]);
+        let crates = vec![dependency("autospy", "0.0.7")];
         let file_io_spy = FileIOSpy::default();
         let http_client_spy = GetRequestSpy::default();
         let mut progress_bar_spy = ProgressBarSpy::default();
@@ -145,27 +660,31 @@ mod tests {
         progress_bar_spy.set_message.returns.set([()]);
 
         http_client_spy.get.returns.set([
-            Ok(include_str!("../tests/data/crate_info.json").to_string()),
+            Ok(include_str!("../tests/data/crates.json").to_string()),
             Err(anyhow::anyhow!("deliberate test error")),
         ]);
 
         progress_bar_spy.increment.returns.set([()]);
 
-        assert!(
+        assert_eq!(
+            [failure(Stage::ReverseDependencies, "deliberate test error")],
             generate_list(
                 crates,
                 &file_io_spy,
                 &http_client_spy,
-                &mut progress_bar_spy
+                &mut progress_bar_spy,
+                &options(OutputFormat::Markdown),
             )
-            .is_ok()
+            .unwrap()
+            .failures
+            .as_slice()
         )
     }
 
     #[test]
-    fn single_crate_required_get_contributor_count_fails_appends_line_with_0_as_contributor_count()
-    {
-        let crates = BTreeSet::from(["autospy".to_string()]);
+    fn single_crate_required_get_contributor_count_fails_appends_line_with_unknown_contributor_count()
+     {
+        let crates = vec![dependency("autospy", "0.0.7")];
         let file_io_spy = FileIOSpy::default();
         let http_client_spy = GetRequestSpy::default();
         let mut progress_bar_spy = ProgressBarSpy::default();
@@ -176,10 +695,60 @@ mod tests {
         progress_bar_spy.set_message.returns.set([()]);
 
         http_client_spy.get.returns.set([
-            Ok(include_str!("../tests/data/crate_info.json").to_string()),
+            Ok(include_str!("../tests/data/crates.json").to_string()),
+            Ok(r#"{ "dependencies": [], "versions": [], "meta": { "total": 32 } }"#.to_string()),
+        ]);
+        http_client_spy
+            .get_response
+            .returns
+            .set([Err(anyhow::anyhow!("deliberate test error"))]);
+
+        file_io_spy.append.returns.set([Ok(())]);
+
+        progress_bar_spy.increment.returns.set([()]);
+
+        assert_eq!(
+            [failure(Stage::GitHub, "deliberate test error")],
+            generate_list(
+                crates,
+                &file_io_spy.clone(),
+                &http_client_spy,
+                &mut progress_bar_spy,
+                &options(OutputFormat::Markdown),
+            )
+            .unwrap()
+            .failures
+            .as_slice()
+        );
+        assert_eq!(
+            [
+                b"|autospy|0.0.7|0.0.8|MIT|1861|?|32|8|15/05/2025|01/07/2025|https://github.com/lhalf/autospy|2025-10-01T12:00:00Z|\n"
+                    .to_vec()
+            ],
+            file_io_spy.append.arguments
+        )
+    }
+
+    #[test]
+    fn single_crate_required_offline_appends_line_without_contributors() {
+        let crates = vec![dependency("autospy", "0.0.7")];
+        let file_io_spy = FileIOSpy::default();
+        let http_client_spy = GetRequestSpy::default();
+        let mut progress_bar_spy = ProgressBarSpy::default();
+
+        file_io_spy.read_to_string.returns.set([Ok(String::new())]);
+
+        progress_bar_spy.set_total.returns.set([()]);
+        progress_bar_spy.set_message.returns.set([()]);
+
+        http_client_spy.get.returns.set([
+            Ok(include_str!("../tests/data/crates.json").to_string()),
             Ok(r#"{ "dependencies": [], "versions": [], "meta": { "total": 32 } }"#.to_string()),
-            Err(anyhow::anyhow!("deliberate test error")),
         ]);
+        http_client_spy.get_response.returns.set([Err(Offline {
+            url: "https://api.github.com/repos/lhalf/autospy/contributors".to_string(),
+        }
+        .into())]);
 
         file_io_spy.append.returns.set([Ok(())]);
 
@@ -190,22 +759,70 @@ mod tests {
                 crates,
                 &file_io_spy.clone(),
                 &http_client_spy,
-                &mut progress_bar_spy
+                &mut progress_bar_spy,
+                &options(OutputFormat::Markdown),
             )
-            .is_ok()
+            .unwrap()
+            .failures
+            .is_empty()
         );
         assert_eq!(
             [
-                b"|autospy|1861|0|32|8|15/05/2025|01/07/2025|https://github.com/lhalf/autospy|\n"
+                b"|autospy|0.0.7|0.0.8|MIT|1861|n/a|32|8|15/05/2025|01/07/2025|https://github.com/lhalf/autospy|2025-10-01T12:00:00Z|\n"
                     .to_vec()
             ],
             file_io_spy.append.arguments
         )
     }
 
+    #[test]
+    fn single_crate_required_get_contributor_count_rate_limited_is_not_written() {
+        let crates = vec![dependency("autospy", "0.0.7")];
+        let file_io_spy = FileIOSpy::default();
+        let http_client_spy = GetRequestSpy::default();
+        let mut progress_bar_spy = ProgressBarSpy::default();
+
+        file_io_spy.read_to_string.returns.set([Ok(String::new())]);
+
+        progress_bar_spy.set_total.returns.set([()]);
+        progress_bar_spy.set_message.returns.set([()]);
+
+        http_client_spy.get.returns.set([
+            Ok(include_str!("../tests/data/crates.json").to_string()),
+            Ok(r#"{ "dependencies": [], "versions": [], "meta": { "total": 32 } }"#.to_string()),
+        ]);
+        http_client_spy.get_response.returns.set([Err(RateLimited {
+            url: "https://api.github.com/repos/lhalf/autospy/contributors".to_string(),
+            retry_after: None,
+            authenticated: false,
+        }
+        .into())]);
+
+        progress_bar_spy.increment.returns.set([()]);
+
+        let generated = generate_list(
+            crates,
+            &file_io_spy.clone(),
+            &http_client_spy,
+            &mut progress_bar_spy,
+            &options(OutputFormat::Markdown),
+        )
+        .unwrap();
+
+        assert!(generated.crates.is_empty());
+        assert_eq!(
+            [failure(
+                Stage::GitHub,
+                "rate limited by: https://api.github.com/repos/lhalf/autospy/contributors, set GITHUB_TOKEN or --github-token for a higher limit"
+            )],
+            generated.failures.as_slice()
+        );
+        assert!(file_io_spy.append.arguments.take().is_empty())
+    }
+
     #[test]
     fn single_crate_required_appending_table_fails() {
-        let crates = BTreeSet::from(["autospy".to_string()]);
+        let crates = vec![dependency("autospy", "0.0.7")];
         let file_io_spy = FileIOSpy::default();
         let http_client_spy = GetRequestSpy::default();
         let mut progress_bar_spy = ProgressBarSpy::default();
@@ -216,10 +833,13 @@ mod tests {
         progress_bar_spy.set_message.returns.set([()]);
 
         http_client_spy.get.returns.set([
-            Ok(include_str!("../tests/data/crate_info.json").to_string()),
+            Ok(include_str!("../tests/data/crates.json").to_string()),
             Ok(r#"{ "dependencies": [], "versions": [], "meta": { "total": 32 } }"#.to_string()),
-            Ok(r#"[1,2,3,4,5]"#.to_string()),
         ]);
+        http_client_spy
+            .get_response
+            .returns
+            .set([Ok(contributors_response())]);
 
         file_io_spy
             .append
@@ -232,7 +852,8 @@ mod tests {
                 crates,
                 &file_io_spy,
                 &http_client_spy,
-                &mut progress_bar_spy
+                &mut progress_bar_spy,
+                &options(OutputFormat::Markdown),
             )
             .unwrap_err()
             .to_string()
@@ -241,7 +862,7 @@ mod tests {
 
     #[test]
     fn single_crate_required_appends_expected_line_to_table() {
-        let crates = BTreeSet::from(["autospy".to_string()]);
+        let crates = vec![dependency("autospy", "0.0.7")];
         let file_io_spy = FileIOSpy::default();
         let http_client_spy = GetRequestSpy::default();
         let mut progress_bar_spy = ProgressBarSpy::default();
@@ -252,10 +873,13 @@ mod tests {
         progress_bar_spy.set_message.returns.set([()]);
 
         http_client_spy.get.returns.set([
-            Ok(include_str!("../tests/data/crate_info.json").to_string()),
+            Ok(include_str!("../tests/data/crates.json").to_string()),
             Ok(r#"{ "dependencies": [], "versions": [], "meta": { "total": 32 } }"#.to_string()),
-            Ok(r#"[1,2,3,4,5]"#.to_string()),
         ]);
+        http_client_spy
+            .get_response
+            .returns
+            .set([Ok(contributors_response())]);
 
         file_io_spy.append.returns.set([Ok(())]);
 
@@ -266,16 +890,704 @@ mod tests {
                 crates,
                 &file_io_spy.clone(),
                 &http_client_spy,
-                &mut progress_bar_spy
+                &mut progress_bar_spy,
+                &options(OutputFormat::Markdown),
             )
             .is_ok()
         );
         assert_eq!(
             [
-                b"|autospy|1861|5|32|8|15/05/2025|01/07/2025|https://github.com/lhalf/autospy|\n"
+                b"|autospy|0.0.7|0.0.8|MIT|1861|5|32|8|15/05/2025|01/07/2025|https://github.com/lhalf/autospy|2025-10-01T12:00:00Z|\n"
                     .to_vec()
             ],
             file_io_spy.append.arguments
         )
     }
+
+    #[test]
+    fn single_crate_required_json_format_writes_document() {
+        let crates = vec![dependency("autospy", "0.0.7")];
+        let file_io_spy = FileIOSpy::default();
+        let http_client_spy = GetRequestSpy::default();
+        let mut progress_bar_spy = ProgressBarSpy::default();
+
+        file_io_spy
+            .read_to_string
+            .returns
+            .set([Ok("[]\n".to_string())]);
+
+        progress_bar_spy.set_total.returns.set([()]);
+        progress_bar_spy.set_message.returns.set([()]);
+
+        http_client_spy.get.returns.set([
+            Ok(include_str!("../tests/data/crates.json").to_string()),
+            Ok(r#"{ "dependencies": [], "versions": [], "meta": { "total": 32 } }"#.to_string()),
+        ]);
+        http_client_spy
+            .get_response
+            .returns
+            .set([Ok(contributors_response())]);
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        progress_bar_spy.increment.returns.set([()]);
+
+        assert!(
+            generate_list(
+                crates,
+                &file_io_spy.clone(),
+                &http_client_spy,
+                &mut progress_bar_spy,
+                &options(OutputFormat::Json),
+            )
+            .is_ok()
+        );
+
+        let document: serde_json::Value =
+            serde_json::from_slice(&file_io_spy.write.arguments.take()[0]).unwrap();
+        assert_eq!(
+            serde_json::json!([{
+                "name": "autospy",
+                "version": "0.0.7",
+                "latest_version": "0.0.8",
+                "license": "MIT",
+                "downloads": 1861,
+                "contributors": 5,
+                "reverse_dependencies": 32,
+                "versions": 8,
+                "created_at": "2025-05-15T13:17:05.242665Z",
+                "updated_at": "2025-07-01T12:45:04.998603Z",
+                "repository": "https://github.com/lhalf/autospy",
+                "fetched_at": "2025-10-01T12:00:00Z"
+            }]),
+            document
+        )
+    }
+
+    #[test]
+    fn existing_crate_version_is_skipped_and_other_version_appended() {
+        let crates = vec![
+            dependency("autospy", "0.0.7"),
+            dependency("autospy", "0.0.8"),
+        ];
+        let file_io_spy = FileIOSpy::default();
+        let http_client_spy = GetRequestSpy::default();
+        let mut progress_bar_spy = ProgressBarSpy::default();
+
+        file_io_spy.read_to_string.returns.set([Ok([
+            OutputFormat::Markdown.heading(Crate::columns()),
+            "|autospy|0.0.7|\n".to_string(),
+        ]
+        .join(""))]);
+
+        progress_bar_spy.set_total.returns.set([()]);
+        progress_bar_spy.set_message.returns.set([()]);
+
+        http_client_spy.get.returns.set([
+            Ok(include_str!("../tests/data/crates.json").to_string()),
+            Ok(r#"{ "dependencies": [], "versions": [], "meta": { "total": 32 } }"#.to_string()),
+        ]);
+        http_client_spy
+            .get_response
+            .returns
+            .set([Ok(contributors_response())]);
+
+        file_io_spy.append.returns.set([Ok(())]);
+
+        progress_bar_spy.increment.returns.set([()]);
+
+        assert!(
+            generate_list(
+                crates,
+                &file_io_spy.clone(),
+                &http_client_spy,
+                &mut progress_bar_spy,
+                &options(OutputFormat::Markdown),
+            )
+            .is_ok()
+        );
+        assert_eq!([1], progress_bar_spy.set_total.arguments);
+        assert_eq!(
+            [b"|autospy|0.0.8|0.0.8|MIT|1861|5|32|8|15/05/2025|01/07/2025|https://github.com/lhalf/autospy|2025-10-01T12:00:00Z|\n"
+                .to_vec()],
+            file_io_spy.append.arguments
+        )
+    }
+
+    #[test]
+    fn existing_crates_are_checked_against_license_policy_without_fetching() {
+        let crates = vec![dependency("autospy", "0.0.7")];
+        let file_io_spy = FileIOSpy::default();
+        let http_client_spy = GetRequestSpy::default();
+        let mut progress_bar_spy = ProgressBarSpy::default();
+
+        file_io_spy
+            .read_to_string
+            .returns
+            .set([Ok("|name|version|\n|-|-|\n|autospy|0.0.7|\n".to_string())]);
+
+        progress_bar_spy.set_total.returns.set([()]);
+
+        let generated = generate_list(
+            crates,
+            &file_io_spy,
+            &http_client_spy,
+            &mut progress_bar_spy,
+            &Options {
+                license_policy: LicensePolicy::new(Vec::new(), vec!["MIT".to_string()]),
+                ..options(OutputFormat::Markdown)
+            },
+        )
+        .unwrap();
+
+        assert!(generated.crates.is_empty());
+        assert_eq!(
+            ["autospy 0.0.7 license MIT is not allowed"],
+            generated.license_violations.as_slice()
+        )
+    }
+
+    #[test]
+    fn reverse_dependencies_are_fetched_for_existing_heading_without_being_selected() {
+        let crates = vec![dependency("autospy", "0.0.7")];
+        let file_io_spy = FileIOSpy::default();
+        let http_client_spy = GetRequestSpy::default();
+        let mut progress_bar_spy = ProgressBarSpy::default();
+
+        file_io_spy.read_to_string.returns.set([Ok(
+            "|name|version|reverse_dependencies|\n|-|-|-|\n".to_string(),
+        )]);
+
+        progress_bar_spy.set_total.returns.set([()]);
+        progress_bar_spy.set_message.returns.set([()]);
+
+        http_client_spy.get.returns.set([
+            Ok(include_str!("../tests/data/crates.json").to_string()),
+            Ok(r#"{ "dependencies": [], "versions": [], "meta": { "total": 32 } }"#.to_string()),
+        ]);
+        http_client_spy
+            .get_response
+            .returns
+            .set([Ok(contributors_response())]);
+
+        file_io_spy.append.returns.set([Ok(())]);
+
+        progress_bar_spy.increment.returns.set([()]);
+
+        generate_list(
+            crates,
+            &file_io_spy.clone(),
+            &http_client_spy,
+            &mut progress_bar_spy,
+            &Options {
+                columns: select_columns(Some(vec!["name".to_string(), "version".to_string()]))
+                    .unwrap(),
+                reverse_dependencies: false,
+                ..options(OutputFormat::Markdown)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            [b"|autospy|0.0.7|32|\n".to_vec()],
+            file_io_spy.append.arguments
+        )
+    }
+
+    #[test]
+    fn fetching_existing_crates_returns_them_without_appending() {
+        let crates = vec![dependency("autospy", "0.0.7")];
+        let file_io_spy = FileIOSpy::default();
+        let http_client_spy = GetRequestSpy::default();
+        let mut progress_bar_spy = ProgressBarSpy::default();
+
+        file_io_spy
+            .read_to_string
+            .returns
+            .set([Ok("|name|version|\n|-|-|\n|autospy|0.0.7|\n".to_string())]);
+
+        progress_bar_spy.set_total.returns.set([()]);
+        progress_bar_spy.set_message.returns.set([()]);
+
+        http_client_spy.get.returns.set([
+            Ok(include_str!("../tests/data/crates.json").to_string()),
+            Ok(r#"{ "dependencies": [], "versions": [], "meta": { "total": 32 } }"#.to_string()),
+        ]);
+        http_client_spy
+            .get_response
+            .returns
+            .set([Ok(contributors_response())]);
+
+        progress_bar_spy.increment.returns.set([()]);
+
+        let fetched = generate_list(
+            crates,
+            &file_io_spy.clone(),
+            &http_client_spy,
+            &mut progress_bar_spy,
+            &Options {
+                fetch_existing: true,
+                ..options(OutputFormat::Markdown)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            [(
+                "autospy".to_string(),
+                "0.0.7".to_string(),
+                Fetched::Present(5)
+            )],
+            fetched
+                .crates
+                .into_iter()
+                .map(|crate_info| (crate_info.name, crate_info.version, crate_info.contributors))
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+        assert!(file_io_spy.append.arguments.take().is_empty())
+    }
+
+    #[test]
+    fn stale_crate_is_rewritten_and_fresh_crate_is_kept() {
+        let crates = vec![
+            dependency("anyhow", "1.0.100"),
+            dependency("autospy", "0.0.7"),
+        ];
+        let file_io_spy = FileIOSpy::default();
+        let http_client_spy = GetRequestSpy::default();
+        let mut progress_bar_spy = ProgressBarSpy::default();
+
+        file_io_spy.read_to_string.returns.set([Ok([
+            OutputFormat::Markdown.heading(Crate::columns()),
+            "|anyhow|1.0.100||||||||||2025-09-30T00:00:00Z|\n".to_string(),
+            "|autospy|0.0.7||||||||||2025-01-01T00:00:00Z|\n".to_string(),
+        ]
+        .join(""))]);
+
+        progress_bar_spy.set_total.returns.set([()]);
+        progress_bar_spy.set_message.returns.set([()]);
+
+        http_client_spy.get.returns.set([
+            Ok(include_str!("../tests/data/crates.json").to_string()),
+            Ok(r#"{ "dependencies": [], "versions": [], "meta": { "total": 32 } }"#.to_string()),
+        ]);
+        http_client_spy
+            .get_response
+            .returns
+            .set([Ok(contributors_response())]);
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        progress_bar_spy.increment.returns.set([()]);
+
+        assert!(
+            generate_list(
+                crates,
+                &file_io_spy.clone(),
+                &http_client_spy,
+                &mut progress_bar_spy,
+                &Options {
+                    refresh_older_than: Some(Duration::from_secs(30 * 24 * 60 * 60)),
+                    ..options(OutputFormat::Markdown)
+                },
+            )
+            .is_ok()
+        );
+        assert_eq!(["autospy "], progress_bar_spy.set_message.arguments);
+        assert_eq!(
+            [[
+                OutputFormat::Markdown.heading(Crate::columns()).as_str(),
+                "|anyhow|1.0.100||||||||||2025-09-30T00:00:00Z|\n",
+                "|autospy|0.0.7|0.0.8|MIT|1861|5|32|8|15/05/2025|01/07/2025|https://github.com/lhalf/autospy|2025-10-01T12:00:00Z|\n"
+            ]
+            .join("")
+            .into_bytes()],
+            file_io_spy.write.arguments
+        )
+    }
+
+    #[test]
+    fn crate_without_fetched_at_is_stale() {
+        let crates = vec![dependency("autospy", "0.0.7")];
+        let file_io_spy = FileIOSpy::default();
+        let http_client_spy = GetRequestSpy::default();
+        let mut progress_bar_spy = ProgressBarSpy::default();
+
+        file_io_spy
+            .read_to_string
+            .returns
+            .set([Ok("|name|version|\n|-|-|\n|autospy|0.0.7|\n".to_string())]);
+
+        progress_bar_spy.set_total.returns.set([()]);
+        progress_bar_spy.set_message.returns.set([()]);
+
+        http_client_spy.get.returns.set([
+            Ok(include_str!("../tests/data/crates.json").to_string()),
+            Ok(r#"{ "dependencies": [], "versions": [], "meta": { "total": 32 } }"#.to_string()),
+        ]);
+        http_client_spy
+            .get_response
+            .returns
+            .set([Ok(contributors_response())]);
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        progress_bar_spy.increment.returns.set([()]);
+
+        assert!(
+            generate_list(
+                crates,
+                &file_io_spy.clone(),
+                &http_client_spy,
+                &mut progress_bar_spy,
+                &Options {
+                    refresh_older_than: Some(Duration::from_secs(30 * 24 * 60 * 60)),
+                    ..options(OutputFormat::Markdown)
+                },
+            )
+            .is_ok()
+        );
+        assert_eq!(1, file_io_spy.write.arguments.take().len())
+    }
+
+    #[test]
+    fn pruning_removes_crates_that_are_no_longer_dependencies() {
+        let crates = vec![dependency("autospy", "0.0.7")];
+        let file_io_spy = FileIOSpy::default();
+        let http_client_spy = GetRequestSpy::default();
+        let mut progress_bar_spy = ProgressBarSpy::default();
+
+        file_io_spy.read_to_string.returns.set([Ok(
+            "|name|version|\n|-|-|\n|anyhow|1.0.100|\n|autospy|0.0.7|\n|autospy|0.0.6|\n"
+                .to_string(),
+        )]);
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        progress_bar_spy.set_total.returns.set([()]);
+
+        let generated = generate_list(
+            crates,
+            &file_io_spy.clone(),
+            &http_client_spy,
+            &mut progress_bar_spy,
+            &Options {
+                prune: true,
+                ..options(OutputFormat::Markdown)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            [
+                ("anyhow".to_string(), "1.0.100".to_string()),
+                ("autospy".to_string(), "0.0.6".to_string())
+            ],
+            generated.removed.as_slice()
+        );
+        assert_eq!([0], progress_bar_spy.set_total.arguments);
+        assert_eq!(
+            [b"|name|version|\n|-|-|\n|autospy|0.0.7|\n".to_vec()],
+            file_io_spy.write.arguments
+        )
+    }
+
+    #[test]
+    fn table_without_version_column_matches_rows_by_name() {
+        let crates = vec![dependency("anyhow", "1.0.100")];
+        let file_io_spy = FileIOSpy::default();
+        let http_client_spy = GetRequestSpy::default();
+        let mut progress_bar_spy = ProgressBarSpy::default();
+        let heading = [
+            "| name        | downloads | contributors | reverse_dependencies | versions | created_at | updated_at | repository                                  |\n",
+            "|-------------|-----------|--------------|----------------------|----------|------------|------------|---------------------------------------------|\n",
+        ]
+        .join("");
+
+        file_io_spy.read_to_string.returns.set([Ok([
+            heading.as_str(),
+            "| anyhow      | 455074655 | 24           | 24558                | 102      | 05/10/2019 | 19/09/2025 | https://github.com/dtolnay/anyhow           |\n",
+            "| chrono      | 393631079 | 30+          | 17491                | 92       | 20/11/2014 | 08/09/2025 | https://github.com/chronotope/chrono        |\n",
+        ]
+        .join(""))]);
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        progress_bar_spy.set_total.returns.set([()]);
+
+        let generated = generate_list(
+            crates,
+            &file_io_spy.clone(),
+            &http_client_spy,
+            &mut progress_bar_spy,
+            &Options {
+                prune: true,
+                ..options(OutputFormat::Markdown)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            [("chrono".to_string(), String::new())],
+            generated.removed.as_slice()
+        );
+        assert_eq!([0], progress_bar_spy.set_total.arguments);
+        assert_eq!(
+            [[
+                heading.as_str(),
+                "| anyhow      | 455074655 | 24           | 24558                | 102      | 05/10/2019 | 19/09/2025 | https://github.com/dtolnay/anyhow           |\n",
+            ]
+            .join("")
+            .into_bytes()],
+            file_io_spy.write.arguments
+        )
+    }
+
+    #[test]
+    fn sorting_orders_existing_crates() {
+        let crates = vec![
+            dependency("anyhow", "1.0.100"),
+            dependency("syn", "2.0.106"),
+        ];
+        let file_io_spy = FileIOSpy::default();
+        let http_client_spy = GetRequestSpy::default();
+        let mut progress_bar_spy = ProgressBarSpy::default();
+
+        file_io_spy.read_to_string.returns.set([Ok(
+            "|name|version|downloads|\n|-|-|-|\n|anyhow|1.0.100|90|\n|syn|2.0.106|900|\n"
+                .to_string(),
+        )]);
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        progress_bar_spy.set_total.returns.set([()]);
+
+        assert!(
+            generate_list(
+                crates,
+                &file_io_spy.clone(),
+                &http_client_spy,
+                &mut progress_bar_spy,
+                &Options {
+                    sort_by: Some("downloads".to_string()),
+                    descending: true,
+                    ..options(OutputFormat::Markdown)
+                },
+            )
+            .is_ok()
+        );
+        assert_eq!(
+            [
+                b"|name|version|downloads|\n|-|-|-|\n|syn|2.0.106|900|\n|anyhow|1.0.100|90|\n"
+                    .to_vec()
+            ],
+            file_io_spy.write.arguments
+        )
+    }
+
+    #[test]
+    fn recreating_keeps_user_cells_of_refetched_crates() {
+        let crates = vec![dependency("autospy", "0.0.7")];
+        let file_io_spy = FileIOSpy::default();
+        let http_client_spy = GetRequestSpy::default();
+        let mut progress_bar_spy = ProgressBarSpy::default();
+
+        file_io_spy.read_to_string.returns.set([Ok(
+            "|name|version|notes|\n|-|-|-|\n|autospy|0.0.7|reviewed|\n|anyhow|1.0.100|old|\n"
+                .to_string(),
+        )]);
+
+        progress_bar_spy.set_total.returns.set([()]);
+        progress_bar_spy.set_message.returns.set([()]);
+
+        http_client_spy.get.returns.set([
+            Ok(include_str!("../tests/data/crates.json").to_string()),
+            Ok(r#"{ "dependencies": [], "versions": [], "meta": { "total": 32 } }"#.to_string()),
+        ]);
+        http_client_spy
+            .get_response
+            .returns
+            .set([Ok(contributors_response())]);
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        progress_bar_spy.increment.returns.set([()]);
+
+        assert!(
+            generate_list(
+                crates,
+                &file_io_spy.clone(),
+                &http_client_spy,
+                &mut progress_bar_spy,
+                &Options {
+                    recreate: true,
+                    ..options(OutputFormat::Markdown)
+                },
+            )
+            .is_ok()
+        );
+        assert_eq!(
+            [[
+                OutputFormat::Markdown
+                    .heading(Crate::columns())
+                    .replace("|fetched_at|", "|fetched_at|notes|")
+                    .replace("|-|\n", "|-|-|\n"),
+                "|autospy|0.0.7|0.0.8|MIT|1861|5|32|8|15/05/2025|01/07/2025|https://github.com/lhalf/autospy|2025-10-01T12:00:00Z|reviewed|\n".to_string(),
+            ]
+            .join("")
+            .into_bytes()],
+            file_io_spy.write.arguments
+        );
+        assert!(file_io_spy.append.arguments.take().is_empty())
+    }
+
+    #[test]
+    fn recreating_without_reaching_crates_io_leaves_output_file() {
+        let crates = vec![dependency("autospy", "0.0.7")];
+        let file_io_spy = FileIOSpy::default();
+        let http_client_spy = GetRequestSpy::default();
+        let mut progress_bar_spy = ProgressBarSpy::default();
+
+        file_io_spy.read_to_string.returns.set([Ok(
+            "|name|version|notes|\n|-|-|-|\n|autospy|0.0.7|reviewed|\n".to_string(),
+        )]);
+
+        progress_bar_spy.set_total.returns.set([()]);
+
+        http_client_spy.get.returns.set([Err(StatusError {
+            url: "https://crates.io/api/v1/crates".to_string(),
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            retry_after: None,
+        }
+        .into())]);
+
+        assert!(
+            generate_list(
+                crates,
+                &file_io_spy.clone(),
+                &http_client_spy,
+                &mut progress_bar_spy,
+                &Options {
+                    recreate: true,
+                    ..options(OutputFormat::Markdown)
+                },
+            )
+            .is_err()
+        );
+        assert!(file_io_spy.write.arguments.take().is_empty())
+    }
+
+    #[test]
+    fn recreating_keeps_row_and_user_cells_of_crate_that_failed() {
+        let crates = vec![dependency("autospy", "0.0.7")];
+        let file_io_spy = FileIOSpy::default();
+        let http_client_spy = GetRequestSpy::default();
+        let mut progress_bar_spy = ProgressBarSpy::default();
+
+        file_io_spy.read_to_string.returns.set([Ok(
+            "|name|version|downloads|notes|\n|-|-|-|-|\n|autospy|0.0.7|1000|reviewed|\n"
+                .to_string(),
+        )]);
+
+        progress_bar_spy.set_total.returns.set([()]);
+        progress_bar_spy.set_message.returns.set([()]);
+
+        http_client_spy.get.returns.set([
+            Ok(include_str!("../tests/data/crates.json").to_string()),
+            Ok(r#"{ "dependencies": [], "versions": [], "meta": { "total": 32 } }"#.to_string()),
+        ]);
+        http_client_spy.get_response.returns.set([Err(RateLimited {
+            url: "https://api.github.com/repos/lhalf/autospy/contributors".to_string(),
+            retry_after: None,
+            authenticated: false,
+        }
+        .into())]);
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        progress_bar_spy.increment.returns.set([()]);
+
+        let generated = generate_list(
+            crates,
+            &file_io_spy.clone(),
+            &http_client_spy,
+            &mut progress_bar_spy,
+            &Options {
+                recreate: true,
+                columns: select_columns(Some(vec![
+                    "name".to_string(),
+                    "version".to_string(),
+                    "downloads".to_string(),
+                ]))
+                .unwrap(),
+                ..options(OutputFormat::Markdown)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(1, generated.failures.len());
+        assert_eq!(
+            [
+                b"|name|version|downloads|notes|\n|-|-|-|-|\n|autospy|0.0.7|1000|reviewed|\n"
+                    .to_vec()
+            ],
+            file_io_spy.write.arguments
+        )
+    }
+
+    #[test]
+    fn multiple_crates_fetched_concurrently_are_appended_in_order() {
+        let crates = vec![
+            dependency("anyhow", "1.0.100"),
+            dependency("autospy", "0.0.8"),
+            dependency("clap", "4.5.51"),
+        ];
+        let file_io_spy = FileIOSpy::default();
+        let http_client_spy = GetRequestSpy::default();
+        let mut progress_bar_spy = ProgressBarSpy::default();
+
+        file_io_spy.read_to_string.returns.set([Ok(String::new())]);
+
+        progress_bar_spy.set_total.returns.set([()]);
+        progress_bar_spy.set_message.returns.set([(), (), ()]);
+
+        http_client_spy.get.returns.set_fn(|url| {
+            if url.ends_with("/reverse_dependencies") {
+                Ok(
+                    r#"{ "dependencies": [], "versions": [], "meta": { "total": 32 } }"#
+                        .to_string(),
+                )
+            } else {
+                Ok(include_str!("../tests/data/crates.json").to_string())
+            }
+        });
+        http_client_spy
+            .get_response
+            .returns
+            .set_fn(|_| Ok(contributors_response()));
+
+        file_io_spy.append.returns.set([Ok(()), Ok(()), Ok(())]);
+
+        progress_bar_spy.increment.returns.set([(), (), ()]);
+
+        assert!(
+            generate_list(
+                crates,
+                &file_io_spy.clone(),
+                &http_client_spy,
+                &mut progress_bar_spy,
+                &Options {
+                    jobs: 3,
+                    ..options(OutputFormat::Markdown)
+                },
+            )
+            .is_ok()
+        );
+        assert_eq!(
+            ["anyhow ", "autospy ", "clap "],
+            progress_bar_spy.set_message.arguments
+        );
+        assert_eq!(3, file_io_spy.append.arguments.take().len())
+    }
 }
@@ -1,45 +1,83 @@
+use crate::fetched::Fetched;
 use crate::http_client::GetRequest;
 use anyhow::{Context, Error};
+use reqwest::Url;
 
 const API_BASE_URL: &str = "https://api.github.com/repos";
 
-pub fn get_contributor_count(http_client: &impl GetRequest, repo_url: &str) -> Result<u16, Error> {
-    let contributors_url = format!(
-        "{API_BASE_URL}/{}/contributors",
-        sanitise_repo_url(repo_url)?
-    );
-
-    match serde_json::from_str::<serde_json::Value>(&http_client.get(&contributors_url)?)
-        .with_context(|| format!("failed to deserialize response from: {contributors_url}"))?
-        .as_array()
-    {
-        Some(contributors) => Ok(contributors.len() as u16),
-        None => Ok(0),
-    }
+/// Requests a single contributor per page, so the number of pages is the number of contributors,
+/// repositories not on GitHub have no contributor count
+pub fn get_contributor_count(
+    http_client: &impl GetRequest,
+    repo_url: &str,
+) -> Result<Fetched<u16>, Error> {
+    let Some(repository) = sanitise_repo_url(repo_url) else {
+        return Ok(Fetched::Absent);
+    };
+    let contributors_url = format!("{API_BASE_URL}/{repository}/contributors?per_page=1");
+
+    let response = http_client.get_response(&contributors_url)?;
+
+    let page = serde_json::from_str::<serde_json::Value>(&response.body)
+        .with_context(|| format!("failed to deserialize response from: {contributors_url}"))?;
+
+    let contributors = match (page.as_array(), response.header("link").and_then(last_page)) {
+        (Some(_), Some(last_page)) => u16::try_from(last_page).unwrap_or(u16::MAX),
+        (Some(contributors), None) => contributors.len() as u16,
+        (None, _) => 0,
+    };
+
+    Ok(Fetched::Present(contributors))
+}
+
+// pagination - https://docs.github.com/en/rest/using-the-rest-api/using-pagination-in-the-rest-api
+fn last_page(link: &str) -> Option<u64> {
+    let last = link
+        .split(',')
+        .find(|link| link.contains(r#"rel="last""#))?
+        .split(';')
+        .next()?
+        .trim()
+        .trim_start_matches('<')
+        .trim_end_matches('>');
+
+    Url::parse(last)
+        .ok()?
+        .query_pairs()
+        .find(|(name, _)| name == "page")?
+        .1
+        .parse()
+        .ok()
 }
 
-fn sanitise_repo_url(repo_url: &str) -> anyhow::Result<&str> {
-    let mut repo_url = repo_url
-        .strip_prefix("https://github.com/")
-        .context("could not extract owner and name from repository url")?;
+fn sanitise_repo_url(repo_url: &str) -> Option<&str> {
+    let mut repo_url = repo_url.strip_prefix("https://github.com/")?;
 
     repo_url = repo_url.strip_suffix(".git").unwrap_or(repo_url);
 
-    Ok(repo_url.strip_suffix("/").unwrap_or(repo_url))
+    Some(repo_url.strip_suffix("/").unwrap_or(repo_url))
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::github::get_contributor_count;
-    use crate::http_client::GetRequestSpy;
+    use crate::fetched::Fetched;
+    use crate::github::{get_contributor_count, last_page};
+    use crate::http_client::{GetRequestSpy, Response};
+    use std::collections::BTreeMap;
+
+    fn response(body: &str) -> Response {
+        Response {
+            body: body.to_string(),
+            headers: BTreeMap::new(),
+        }
+    }
 
     #[test]
-    fn repo_url_with_invalid_base_url() {
+    fn repo_url_not_on_github_has_no_contributor_count() {
         assert_eq!(
-            "could not extract owner and name from repository url",
+            Fetched::Absent,
             get_contributor_count(&GetRequestSpy::default(), "http://invalid/url/user/repo")
-                .unwrap_err()
-                .to_string()
+                .unwrap()
         )
     }
 
@@ -47,7 +85,7 @@ mod tests {
     fn fails_to_reach_contributor_url() {
         let spy = GetRequestSpy::default();
 
-        spy.get
+        spy.get_response
             .returns
             .set([Err(anyhow::anyhow!("deliberate test error"))]);
 
@@ -63,10 +101,10 @@ mod tests {
     fn contributor_url_returns_invalid_json() {
         let spy = GetRequestSpy::default();
 
-        spy.get.returns.set([Ok("invalid JSON".to_string())]);
+        spy.get_response.returns.set([Ok(response("invalid JSON"))]);
 
         assert_eq!(
-            "failed to deserialize response from: https://api.github.com/repos/invalid/json/contributors",
+            "failed to deserialize response from: https://api.github.com/repos/invalid/json/contributors?per_page=1",
             get_contributor_count(&spy, "https://github.com/invalid/json")
                 .unwrap_err()
                 .to_string()
@@ -77,12 +115,12 @@ mod tests {
     fn contributor_url_returning_non_array_json_returns_0_contributors() {
         let spy = GetRequestSpy::default();
 
-        spy.get.returns.set([Ok(
-            r#"{"not_array": 10, "actually_object": 100}"#.to_string()
-        )]);
+        spy.get_response
+            .returns
+            .set([Ok(response(r#"{"not_array": 10, "actually_object": 100}"#))]);
 
         assert_eq!(
-            0,
+            Fetched::Present(0),
             get_contributor_count(&spy, "https://github.com/not/array").unwrap()
         )
     }
@@ -91,10 +129,12 @@ mod tests {
     fn contributor_url_returning_valid_json_array() {
         let spy = GetRequestSpy::default();
 
-        spy.get.returns.set([Ok(r#"[1,2,3,4,5]"#.to_string())]);
+        spy.get_response
+            .returns
+            .set([Ok(response(r#"[1,2,3,4,5]"#))]);
 
         assert_eq!(
-            5,
+            Fetched::Present(5),
             get_contributor_count(&spy, "https://github.com/valid/array").unwrap()
         )
     }
@@ -103,10 +143,10 @@ mod tests {
     fn contributor_url_ending_with_git_returning_valid_json_array() {
         let spy = GetRequestSpy::default();
 
-        spy.get.returns.set([Ok(r#"[1,2]"#.to_string())]);
+        spy.get_response.returns.set([Ok(response(r#"[1,2]"#))]);
 
         assert_eq!(
-            2,
+            Fetched::Present(2),
             get_contributor_count(&spy, "https://github.com/valid/repo.git").unwrap()
         )
     }
@@ -115,11 +155,46 @@ mod tests {
     fn contributor_url_ending_with_slash_returning_valid_json_array() {
         let spy = GetRequestSpy::default();
 
-        spy.get.returns.set([Ok(r#"[1,2,3]"#.to_string())]);
+        spy.get_response.returns.set([Ok(response(r#"[1,2,3]"#))]);
 
         assert_eq!(
-            3,
+            Fetched::Present(3),
             get_contributor_count(&spy, "https://github.com/valid/repo/").unwrap()
         )
     }
+
+    #[test]
+    fn contributor_count_is_last_page_number() {
+        let spy = GetRequestSpy::default();
+
+        spy.get_response.returns.set([Ok(Response {
+            body: r#"[1]"#.to_string(),
+            headers: BTreeMap::from([(
+                "link".to_string(),
+                r#"<https://api.github.com/repositories/1/contributors?per_page=1&page=2>; rel="next", <https://api.github.com/repositories/1/contributors?per_page=1&page=4012>; rel="last""#.to_string(),
+            )]),
+        })]);
+
+        assert_eq!(
+            Fetched::Present(4012),
+            get_contributor_count(&spy, "https://github.com/many/contributors").unwrap()
+        );
+        assert_eq!(
+            [
+                "https://api.github.com/repos/many/contributors/contributors?per_page=1"
+                    .to_string()
+            ],
+            spy.get_response.arguments
+        )
+    }
+
+    #[test]
+    fn link_without_last_page_has_no_last_page() {
+        assert_eq!(
+            None,
+            last_page(
+                r#"<https://api.github.com/repositories/1/contributors?per_page=1&page=1>; rel="prev""#
+            )
+        )
+    }
 }
@@ -1,35 +1,304 @@
 use anyhow::Context;
 use reqwest::blocking::Client;
+use reqwest::header::HeaderMap;
+use reqwest::{StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 pub const USER_AGENT: &str = "moc.kooltuo@tsil-tsurt";
 
+const GITHUB_API_HOST: &str = "api.github.com";
+
 #[cfg_attr(test, autospy::autospy)]
 pub trait GetRequest {
     fn get(&self, url: &str) -> Result<String, anyhow::Error>;
+    fn get_response(&self, url: &str) -> Result<Response, anyhow::Error>;
 }
 
-impl GetRequest for Client {
+impl<T: GetRequest + ?Sized> GetRequest for Box<T> {
     fn get(&self, url: &str) -> Result<String, anyhow::Error> {
-        let request = self
-            .get(url)
+        (**self).get(url)
+    }
+
+    fn get_response(&self, url: &str) -> Result<Response, anyhow::Error> {
+        (**self).get_response(url)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct Response {
+    pub body: String,
+    /// By lowercase name
+    pub headers: BTreeMap<String, String>,
+}
+
+impl Response {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).map(String::as_str)
+    }
+}
+
+pub struct HttpClient {
+    client: Client,
+    /// Only sent to the GitHub api
+    github_token: Option<String>,
+}
+
+/// The host refused the request until its rate limit resets
+#[derive(Debug, PartialEq)]
+pub struct RateLimited {
+    pub url: String,
+    pub retry_after: Option<Duration>,
+    pub authenticated: bool,
+}
+
+impl std::fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "rate limited by: {}", self.url)?;
+        if let Some(retry_after) = self.retry_after {
+            write!(f, ", resets in {}s", retry_after.as_secs())?;
+        }
+        if !self.authenticated && is_github_api(&self.url) {
+            write!(f, ", set GITHUB_TOKEN or --github-token for a higher limit")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+/// The host responded with an error status other than a rate limit
+#[derive(Debug, PartialEq)]
+pub struct StatusError {
+    pub url: String,
+    pub status: StatusCode,
+    pub retry_after: Option<Duration>,
+}
+
+impl std::fmt::Display for StatusError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid response from: {}, {}", self.url, self.status)
+    }
+}
+
+impl std::error::Error for StatusError {}
+
+impl HttpClient {
+    fn send(&self, url: &str) -> anyhow::Result<reqwest::blocking::Response> {
+        let token = self.github_token.as_ref().filter(|_| is_github_api(url));
+
+        let mut request = self.client.get(url);
+        if let Some(token) = token {
+            request = request.bearer_auth(token);
+        }
+        let request = request
             .build()
             .with_context(|| format!("failed to build request to: {url}"))?;
 
         let response = self
+            .client
             .execute(request)
-            .with_context(|| format!("failed to send request to: {url}"))?
-            .error_for_status()
-            .with_context(|| format!("invalid response from: {url}"))?;
+            .with_context(|| format!("failed to send request to: {url}"))?;
+
+        if let Some(retry_after) =
+            rate_limit_reset(response.status(), response.headers(), SystemTime::now())
+        {
+            return Err(RateLimited {
+                url: url.to_string(),
+                retry_after,
+                authenticated: token.is_some(),
+            }
+            .into());
+        }
+
+        let status = response.status();
+        if status.is_client_error() || status.is_server_error() {
+            return Err(StatusError {
+                url: url.to_string(),
+                status,
+                retry_after: header_secs(response.headers(), "retry-after")
+                    .map(Duration::from_secs),
+            }
+            .into());
+        }
 
-        response
+        Ok(response)
+    }
+}
+
+impl GetRequest for HttpClient {
+    fn get(&self, url: &str) -> Result<String, anyhow::Error> {
+        self.send(url)?
             .text_with_charset("utf-8")
             .with_context(|| format!("response from {url} contained invalid characters"))
     }
+
+    fn get_response(&self, url: &str) -> Result<Response, anyhow::Error> {
+        let response = self.send(url)?;
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        Ok(Response {
+            body: response
+                .text_with_charset("utf-8")
+                .with_context(|| format!("response from {url} contained invalid characters"))?,
+            headers,
+        })
+    }
+}
+
+fn is_github_api(url: &str) -> bool {
+    Url::parse(url).is_ok_and(|url| url.host_str() == Some(GITHUB_API_HOST))
+}
+
+/// Whether the response is a rate limit refusal, with how long until the limit resets if the host said
+fn rate_limit_reset(
+    status: StatusCode,
+    headers: &HeaderMap,
+    now: SystemTime,
+) -> Option<Option<Duration>> {
+    let header = |name: &str| header_secs(headers, name);
+    let is_rate_limited = status == StatusCode::TOO_MANY_REQUESTS
+        || (status == StatusCode::FORBIDDEN && header("x-ratelimit-remaining") == Some(0));
+    if !is_rate_limited {
+        return None;
+    }
+
+    Some(header("retry-after").map(Duration::from_secs).or_else(|| {
+        let reset = UNIX_EPOCH + Duration::from_secs(header("x-ratelimit-reset")?);
+        Some(reset.duration_since(now).unwrap_or_default())
+    }))
 }
 
-pub fn build() -> anyhow::Result<Client> {
-    Client::builder()
-        .user_agent(USER_AGENT.chars().rev().collect::<String>())
-        .build()
-        .context("failed to build api client")
+fn header_secs(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+pub fn build(github_token: Option<String>) -> anyhow::Result<HttpClient> {
+    Ok(HttpClient {
+        client: Client::builder()
+            .user_agent(USER_AGENT.chars().rev().collect::<String>())
+            .build()
+            .context("failed to build api client")?,
+        github_token,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::http_client::{RateLimited, is_github_api, rate_limit_reset};
+    use reqwest::StatusCode;
+    use reqwest::header::{HeaderMap, HeaderValue};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    const NOW: Duration = Duration::from_secs(1_760_000_000);
+
+    fn headers(headers: &[(&'static str, &'static str)]) -> HeaderMap {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                (
+                    reqwest::header::HeaderName::from_static(name),
+                    HeaderValue::from_static(value),
+                )
+            })
+            .collect()
+    }
+
+    fn reset(status: StatusCode, headers: &HeaderMap) -> Option<Option<Duration>> {
+        rate_limit_reset(status, headers, UNIX_EPOCH + NOW)
+    }
+
+    #[test]
+    fn only_github_api_is_github_api() {
+        assert!(is_github_api(
+            "https://api.github.com/repos/lhalf/trust-list/contributors"
+        ));
+        assert!(!is_github_api("https://crates.io/api/v1/crates/autospy"));
+        assert!(!is_github_api("https://api.github.com.example.com/repos"))
+    }
+
+    #[test]
+    fn forbidden_with_no_remaining_requests_waits_until_reset() {
+        assert_eq!(
+            Some(Some(Duration::from_secs(90))),
+            reset(
+                StatusCode::FORBIDDEN,
+                &headers(&[
+                    ("x-ratelimit-remaining", "0"),
+                    ("x-ratelimit-reset", "1760000090")
+                ])
+            )
+        )
+    }
+
+    #[test]
+    fn retry_after_takes_precedence_over_reset() {
+        assert_eq!(
+            Some(Some(Duration::from_secs(30))),
+            reset(
+                StatusCode::TOO_MANY_REQUESTS,
+                &headers(&[("retry-after", "30"), ("x-ratelimit-reset", "1760000090")])
+            )
+        )
+    }
+
+    #[test]
+    fn too_many_requests_without_reset_is_rate_limited() {
+        assert_eq!(
+            Some(None),
+            reset(StatusCode::TOO_MANY_REQUESTS, &HeaderMap::new())
+        )
+    }
+
+    #[test]
+    fn reset_in_the_past_does_not_wait() {
+        assert_eq!(
+            Some(Some(Duration::ZERO)),
+            reset(
+                StatusCode::FORBIDDEN,
+                &headers(&[
+                    ("x-ratelimit-remaining", "0"),
+                    ("x-ratelimit-reset", "1759999990")
+                ])
+            )
+        )
+    }
+
+    #[test]
+    fn forbidden_with_remaining_requests_is_not_rate_limited() {
+        assert_eq!(
+            None,
+            reset(
+                StatusCode::FORBIDDEN,
+                &headers(&[("x-ratelimit-remaining", "12")])
+            )
+        );
+        assert_eq!(None, reset(StatusCode::FORBIDDEN, &HeaderMap::new()))
+    }
+
+    #[test]
+    fn unauthenticated_github_rate_limit_suggests_token() {
+        assert_eq!(
+            "rate limited by: https://api.github.com/repos/a/b/contributors, resets in 90s, set GITHUB_TOKEN or --github-token for a higher limit",
+            RateLimited {
+                url: "https://api.github.com/repos/a/b/contributors".to_string(),
+                retry_after: Some(Duration::from_secs(90)),
+                authenticated: false,
+            }
+            .to_string()
+        );
+        assert_eq!(
+            "rate limited by: https://api.github.com/repos/a/b/contributors",
+            RateLimited {
+                url: "https://api.github.com/repos/a/b/contributors".to_string(),
+                retry_after: None,
+                authenticated: true,
+            }
+            .to_string()
+        )
+    }
 }
@@ -0,0 +1,301 @@
+use crate::cargo_metadata::Dependency;
+use anyhow::Context;
+use std::collections::BTreeSet;
+
+#[derive(Default)]
+pub struct LicensePolicy {
+    allowed: BTreeSet<String>,
+    denied: BTreeSet<String>,
+}
+
+impl LicensePolicy {
+    pub fn new(allowed: Vec<String>, denied: Vec<String>) -> Self {
+        Self {
+            allowed: allowed.into_iter().collect(),
+            denied: denied.into_iter().collect(),
+        }
+    }
+
+    /// Checks the license of every dependency from its manifest, so no crate needs fetching
+    pub fn violations(&self, dependencies: &[Dependency]) -> Vec<String> {
+        if self.allowed.is_empty() && self.denied.is_empty() {
+            return Vec::new();
+        }
+
+        dependencies
+            .iter()
+            .filter_map(|dependency| {
+                let name = format!("{} {}", dependency.name, dependency.version);
+                match dependency.license.as_deref() {
+                    None if self.allowed.is_empty() => None,
+                    None => Some(format!("{name} has no license")),
+                    Some(expression) => match self.permits(expression) {
+                        Ok(true) => None,
+                        Ok(false) => Some(format!("{name} license {expression} is not allowed")),
+                        Err(error) => Some(format!("{name} {error:#}")),
+                    },
+                }
+            })
+            .collect()
+    }
+
+    fn permits(&self, expression: &str) -> anyhow::Result<bool> {
+        Ok(parse(expression)
+            .with_context(|| format!("license {expression} could not be parsed"))?
+            .is_satisfied_by(&|license| {
+                !self.denied.contains(license)
+                    && (self.allowed.is_empty() || self.allowed.contains(license))
+            }))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Expression {
+    License(String),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+}
+
+impl Expression {
+    fn is_satisfied_by(&self, permitted: &impl Fn(&str) -> bool) -> bool {
+        match self {
+            Self::License(license) => permitted(license),
+            Self::And(left, right) => {
+                left.is_satisfied_by(permitted) && right.is_satisfied_by(permitted)
+            }
+            Self::Or(left, right) => {
+                left.is_satisfied_by(permitted) || right.is_satisfied_by(permitted)
+            }
+        }
+    }
+}
+
+// spdx license expressions - https://spdx.github.io/spdx-spec/v3.0.1/annexes/spdx-license-expressions/
+fn parse(expression: &str) -> anyhow::Result<Expression> {
+    // crates.io accepts the legacy "MIT/Apache-2.0" form meaning either license
+    let expression = expression
+        .replace('/', " OR ")
+        .replace('(', " ( ")
+        .replace(')', " ) ");
+    let mut tokens = expression.split_whitespace().peekable();
+
+    let parsed = parse_or(&mut tokens)?;
+
+    match tokens.next() {
+        Some(token) => anyhow::bail!("unexpected {token}"),
+        None => Ok(parsed),
+    }
+}
+
+type Tokens<'a> = std::iter::Peekable<std::str::SplitWhitespace<'a>>;
+
+fn parse_or(tokens: &mut Tokens) -> anyhow::Result<Expression> {
+    let mut expression = parse_and(tokens)?;
+    while tokens
+        .next_if(|token| token.eq_ignore_ascii_case("OR"))
+        .is_some()
+    {
+        expression = Expression::Or(Box::new(expression), Box::new(parse_and(tokens)?));
+    }
+    Ok(expression)
+}
+
+fn parse_and(tokens: &mut Tokens) -> anyhow::Result<Expression> {
+    let mut expression = parse_with(tokens)?;
+    while tokens
+        .next_if(|token| token.eq_ignore_ascii_case("AND"))
+        .is_some()
+    {
+        expression = Expression::And(Box::new(expression), Box::new(parse_with(tokens)?));
+    }
+    Ok(expression)
+}
+
+fn parse_with(tokens: &mut Tokens) -> anyhow::Result<Expression> {
+    let expression = parse_license(tokens)?;
+    // exceptions only grant additional permissions, so the license alone decides
+    if tokens
+        .next_if(|token| token.eq_ignore_ascii_case("WITH"))
+        .is_some()
+    {
+        tokens.next().context("expected exception after WITH")?;
+    }
+    Ok(expression)
+}
+
+fn parse_license(tokens: &mut Tokens) -> anyhow::Result<Expression> {
+    match tokens.next() {
+        Some("(") => {
+            let expression = parse_or(tokens)?;
+            match tokens.next() {
+                Some(")") => Ok(expression),
+                _ => anyhow::bail!("expected )"),
+            }
+        }
+        Some(token) if is_operator(token) || token == ")" => anyhow::bail!("unexpected {token}"),
+        // "or later" versions are matched on the base license identifier
+        Some(token) => Ok(Expression::License(
+            token.strip_suffix('+').unwrap_or(token).to_string(),
+        )),
+        None => anyhow::bail!("expected license"),
+    }
+}
+
+fn is_operator(token: &str) -> bool {
+    ["AND", "OR", "WITH"]
+        .iter()
+        .any(|operator| token.eq_ignore_ascii_case(operator))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cargo_metadata::{Dependency, DependencyKind, Source};
+    use crate::license::{Expression, LicensePolicy, parse};
+    use std::collections::BTreeSet;
+
+    fn license(license: &str) -> Box<Expression> {
+        Box::new(Expression::License(license.to_string()))
+    }
+
+    fn crate_with_license(license: Option<&str>) -> Dependency {
+        Dependency {
+            name: "example".to_string(),
+            version: "1.0.0".to_string(),
+            license: license.map(String::from),
+            source: Source::Registry(
+                "registry+https://github.com/rust-lang/crates.io-index".to_string(),
+            ),
+            kinds: BTreeSet::from([DependencyKind::Normal]),
+        }
+    }
+
+    fn policy(allowed: &[&str], denied: &[&str]) -> LicensePolicy {
+        LicensePolicy::new(
+            allowed.iter().map(|license| license.to_string()).collect(),
+            denied.iter().map(|license| license.to_string()).collect(),
+        )
+    }
+
+    #[test]
+    fn parses_single_license() {
+        assert_eq!(*license("MIT"), parse("MIT").unwrap())
+    }
+
+    #[test]
+    fn parses_and_with_higher_precedence_than_or() {
+        assert_eq!(
+            Expression::Or(
+                license("MIT"),
+                Box::new(Expression::And(
+                    license("Apache-2.0"),
+                    license("BSD-3-Clause")
+                ))
+            ),
+            parse("MIT OR Apache-2.0 AND BSD-3-Clause").unwrap()
+        )
+    }
+
+    #[test]
+    fn parses_parentheses_and_exceptions() {
+        assert_eq!(
+            Expression::And(
+                Box::new(Expression::Or(license("MIT"), license("Apache-2.0"))),
+                license("Unicode-3.0")
+            ),
+            parse("(MIT OR Apache-2.0 WITH LLVM-exception) AND Unicode-3.0").unwrap()
+        )
+    }
+
+    #[test]
+    fn parses_legacy_slash_separator() {
+        assert_eq!(
+            Expression::Or(license("MIT"), license("Apache-2.0")),
+            parse("MIT/Apache-2.0").unwrap()
+        )
+    }
+
+    #[test]
+    fn parses_or_later_as_base_license() {
+        assert_eq!(*license("GPL-2.0"), parse("GPL-2.0+").unwrap())
+    }
+
+    #[test]
+    fn fails_to_parse_unbalanced_parentheses() {
+        assert_eq!(
+            "expected )",
+            parse("(MIT OR Apache-2.0").unwrap_err().to_string()
+        )
+    }
+
+    #[test]
+    fn fails_to_parse_dangling_operator() {
+        assert_eq!("expected license", parse("MIT OR").unwrap_err().to_string())
+    }
+
+    #[test]
+    fn no_policy_has_no_violations() {
+        assert!(
+            policy(&[], &[])
+                .violations(&[crate_with_license(None)])
+                .is_empty()
+        )
+    }
+
+    #[test]
+    fn either_license_allowed_satisfies_or() {
+        assert!(
+            policy(&["MIT"], &[])
+                .violations(&[crate_with_license(Some("MIT OR Apache-2.0"))])
+                .is_empty()
+        )
+    }
+
+    #[test]
+    fn both_licenses_must_be_allowed_to_satisfy_and() {
+        assert_eq!(
+            ["example 1.0.0 license MIT AND Apache-2.0 is not allowed"],
+            policy(&["MIT"], &[])
+                .violations(&[crate_with_license(Some("MIT AND Apache-2.0"))])
+                .as_slice()
+        )
+    }
+
+    #[test]
+    fn denied_license_is_avoided_through_or() {
+        assert!(
+            policy(&[], &["GPL-3.0"])
+                .violations(&[crate_with_license(Some("GPL-3.0 OR MIT"))])
+                .is_empty()
+        )
+    }
+
+    #[test]
+    fn denied_license_is_a_violation() {
+        assert_eq!(
+            ["example 1.0.0 license GPL-3.0-only is not allowed"],
+            policy(&[], &["GPL-3.0-only"])
+                .violations(&[crate_with_license(Some("GPL-3.0-only"))])
+                .as_slice()
+        )
+    }
+
+    #[test]
+    fn missing_license_is_a_violation_when_licenses_are_allowed() {
+        assert_eq!(
+            ["example 1.0.0 has no license"],
+            policy(&["MIT"], &[])
+                .violations(&[crate_with_license(None)])
+                .as_slice()
+        )
+    }
+
+    #[test]
+    fn invalid_license_expression_is_a_violation() {
+        assert_eq!(
+            ["example 1.0.0 license MIT OR could not be parsed: expected license"],
+            policy(&["MIT"], &[])
+                .violations(&[crate_with_license(Some("MIT OR"))])
+                .as_slice()
+        )
+    }
+}
@@ -1,27 +1,83 @@
+use crate::cache::{CachedClient, DiskCache};
+use crate::config::Config;
+use crate::error::{Categorise, Category, Error};
 use crate::file_io::OutputFile;
-use crate::generate_list::generate_list;
+use crate::generate_list::{GeneratedList, Options, generate_list};
+use crate::http_client::GetRequest;
+use crate::license::LicensePolicy;
+use crate::offline_db::OfflineDatabase;
+use crate::output_format::OutputFormat;
+use crate::rate_limit::{RateLimitedClient, SystemClock, Waits};
+use crate::retry::RetryingClient;
+use anyhow::Context;
+use chrono::{SubsecRound, Utc};
 use clap::Parser;
 use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::PoisonError;
+use std::time::Duration;
 
-mod cargo_tree;
+mod cache;
+mod cargo_metadata;
+mod config;
 mod crates_io;
+mod duration;
+mod error;
+mod failure;
+mod fetched;
 mod file_io;
 mod generate_list;
 mod github;
 mod http_client;
+mod license;
+mod markdown;
+mod offline_db;
+mod output_format;
+mod policy;
 mod progress_bar;
+mod rate_limit;
+mod retry;
+mod scheduler;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
-    /// The output filename, appended with .md
+    /// The output filename, appended with the format extension
     #[arg(short, long, default_value_t = String::from("trust-list"))]
     output_file: String,
 
-    /// Recreate table [default: appends new dependencies]
+    /// The output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Markdown)]
+    format: OutputFormat,
+
+    /// Write the table between <!-- trust-list:start --> and <!-- trust-list:end --> in the specified markdown file
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["output_file", "format"])]
+    embed: Option<PathBuf>,
+
+    /// Select and order the generated columns, comma separated [default: all columns]
+    #[arg(long, value_name = "COLUMNS", value_delimiter = ',')]
+    columns: Option<Vec<String>>,
+
+    /// Recreate table, keeping columns users added [default: appends new dependencies]
     #[arg(short, long)]
     recreate: bool,
 
+    /// Rewrite rows fetched longer ago than this, in s, m, h or d [default: existing rows are kept]
+    #[arg(long, value_name = "DURATION", value_parser = duration::parse)]
+    refresh_older_than: Option<Duration>,
+
+    /// Remove rows for crates that are no longer dependencies [default: rows are kept]
+    #[arg(long)]
+    prune: bool,
+
+    /// Sort the whole table by the column, ascending unless --descending [default: rows are appended]
+    #[arg(long, value_name = "COLUMN")]
+    sort_by: Option<String>,
+
+    /// Sort by --sort-by in descending order
+    #[arg(long, requires = "sort_by")]
+    descending: bool,
+
     /// The depth of dependencies to collect information on [default: all sub dependencies]
     #[arg(short = 'D', long)]
     depth: Option<u8>,
@@ -37,28 +93,217 @@ struct Args {
     /// Exclude specified workspace [default: all included]
     #[arg(short, long)]
     exclude: Vec<String>,
+
+    /// Allow specified SPDX license, fails if a dependency can't satisfy an allowed license [default: all allowed]
+    #[arg(long)]
+    allow_license: Vec<String>,
+
+    /// Deny specified SPDX license, fails if a dependency can only satisfy a denied license [default: none denied]
+    #[arg(long)]
+    deny_license: Vec<String>,
+
+    /// Check every dependency against the policy file, fails if any threshold is not met
+    #[arg(short, long)]
+    check: bool,
+
+    /// The policy file, thresholds are used by --check and columns unless --columns is given
+    #[arg(short, long, default_value = "trust-list.toml")]
+    policy: PathBuf,
+
+    /// The number of dependencies to collect information on concurrently
+    #[arg(short, long, default_value_t = 4, value_parser = clap::value_parser!(u8).range(1..))]
+    jobs: u8,
+
+    /// The GitHub token contributor counts are requested with [default: anonymous, limited to 60 requests an hour]
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        env = "GITHUB_TOKEN",
+        hide_env_values = true
+    )]
+    github_token: Option<String>,
+
+    /// Wait for rate limits to reset instead of failing the affected dependencies
+    #[arg(long)]
+    wait_for_rate_limit: bool,
+
+    /// Exit successfully when some crates could not be fetched [default: exits with status 3]
+    #[arg(long)]
+    allow_partial: bool,
+
+    /// Write the summary of crates that could not be fetched to the file, empty if there were none
+    #[arg(long, value_name = "FILE")]
+    failures_file: Option<PathBuf>,
+
+    /// How many times a request is retried after a network error, server error or short rate limit
+    #[arg(long, default_value_t = 3)]
+    retries: u8,
+
+    /// Answer crates.io requests from its database dump, the tarball or the directory it extracts to, contributors are not fetched
+    #[arg(long, value_name = "PATH")]
+    offline_db: Option<PathBuf>,
+
+    /// The directory api responses are cached in [default: user cache directory]
+    #[arg(long)]
+    cache_dir: Option<PathBuf>,
+
+    /// How long cached api responses are reused for, in s, m, h or d [0 always refetches]
+    #[arg(long, value_name = "DURATION", default_value = "24h", value_parser = duration::parse)]
+    cache_ttl: Duration,
 }
 
-fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
+fn main() -> ExitCode {
+    match run(Args::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("Error: {error}");
+            ExitCode::from(error.category.exit_code())
+        }
+    }
+}
+
+fn run(args: Args) -> Result<(), Error> {
+    // the policy file is optional unless checking against it
+    let config = match args.check || args.policy.exists() {
+        true => Config::load(&args.policy).category(Category::Policy)?,
+        false => Config::default(),
+    };
+
+    let columns =
+        output_format::select_columns(args.columns.or(config.columns)).category(Category::Usage)?;
+    // without it every row would be stale and refetched on every run
+    if args.refresh_older_than.is_some() && !columns.iter().any(|column| column == "fetched_at") {
+        return Err(Error::new(
+            Category::Usage,
+            anyhow::anyhow!("columns must include fetched_at to use --refresh-older-than"),
+        ));
+    }
+
+    let output_file = match args.embed {
+        Some(path) => OutputFile::embedded(path, &columns),
+        None => OutputFile::new(
+            PathBuf::from(format!("{}.{}", args.output_file, args.format.extension())),
+            args.format,
+            &columns,
+        ),
+    }
+    .category(Category::FileIo)?;
 
-    let output_file = OutputFile::new(
-        PathBuf::from(format!("{}.md", args.output_file)),
-        args.recreate,
-    )?;
+    let dependencies = cargo_metadata::dependencies(args.depth, args.dev, args.build, args.exclude)
+        .category(Category::Cargo)?;
 
-    let http_client = http_client::build()?;
+    let waits = Waits::default();
+    let http_client: Box<dyn GetRequest + Sync> = match args.offline_db {
+        Some(path) => Box::new(
+            OfflineDatabase::load(
+                &path,
+                &dependencies
+                    .iter()
+                    .map(|dependency| dependency.name.clone())
+                    .collect(),
+            )
+            .map_err(|error| Error::classify(error, Category::FileIo))?,
+        ),
+        None => {
+            let cache_dir = match args.cache_dir {
+                Some(cache_dir) => cache_dir,
+                None => cache::default_dir()
+                    .context("could not find user cache directory, specify one with --cache-dir")
+                    .category(Category::FileIo)?,
+            };
 
-    let crates_names = cargo_tree::crate_names(args.depth, args.dev, args.build, args.exclude)?;
+            // retries go through the rate limiter so they keep to each host's budget
+            Box::new(CachedClient::new(
+                RetryingClient::new(
+                    RateLimitedClient::new(
+                        http_client::build(args.github_token).category(Category::Http)?,
+                        SystemClock,
+                        args.wait_for_rate_limit,
+                        waits.clone(),
+                    ),
+                    SystemClock,
+                    args.retries,
+                ),
+                DiskCache::new(cache_dir),
+                args.cache_ttl,
+            ))
+        }
+    };
 
     let mut progress_bar = progress_bar::build();
 
-    if let Err(error) = generate_list(crates_names, &output_file, &http_client, &mut progress_bar) {
-        panic!("failed to generate trust list: {error:?}")
-    }
+    let GeneratedList {
+        crates,
+        failures,
+        license_violations,
+        removed,
+    } = generate_list(
+        dependencies,
+        &output_file,
+        &http_client,
+        &mut progress_bar,
+        &Options {
+            output_format: args.format,
+            columns,
+            recreate: args.recreate,
+            fetch_existing: args.check,
+            refresh_older_than: args.refresh_older_than,
+            prune: args.prune,
+            sort_by: args.sort_by,
+            descending: args.descending,
+            reverse_dependencies: args.check && config.policy.checks_reverse_dependencies(),
+            license_policy: LicensePolicy::new(args.allow_license, args.deny_license),
+            jobs: args.jobs as usize,
+            now: Utc::now().trunc_subsecs(0),
+        },
+    )
+    .map_err(|error| Error::classify(error, Category::FileIo))?;
 
     progress_bar.finish_print(output_file.path.to_str().unwrap_or_default());
 
+    removed
+        .iter()
+        // rows of tables without a version column have none
+        .for_each(|(name, version)| println!("{}", format!("removed {name} {version}").trim_end()));
+    waits
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .iter()
+        .for_each(|wait| println!("{wait}, waited"));
+
+    let summary = failure::summary(&failures);
+    if !summary.is_empty() {
+        print!("\n{summary}");
+    }
+    if let Some(path) = &args.failures_file {
+        std::fs::write(path, &summary)
+            .with_context(|| format!("failed to write failures file: {}", path.display()))
+            .category(Category::FileIo)?;
+    }
+
+    let mut violations = license_violations;
+    if args.check {
+        violations.extend(config.policy.violations(&crates, Utc::now()));
+    }
+
+    if !violations.is_empty() {
+        println!();
+        violations
+            .iter()
+            .for_each(|violation| println!("{violation}"));
+        return Err(Error::new(
+            Category::Policy,
+            anyhow::anyhow!("{} policy violations", violations.len()),
+        ));
+    }
+
+    if !failures.is_empty() && !args.allow_partial {
+        return Err(Error::new(
+            Category::Incomplete,
+            anyhow::anyhow!("the list is incomplete, pass --allow-partial to accept it"),
+        ));
+    }
+
     Ok(())
 }
 
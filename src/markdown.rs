@@ -0,0 +1,204 @@
+// github flavored markdown tables - https://github.github.com/gfm/#tables-extension-
+use std::ops::Range;
+
+pub fn row<T: AsRef<str>>(cells: &[T]) -> String {
+    cells
+        .iter()
+        .map(|cell| cell.as_ref().replace('|', "\\|"))
+        .fold(String::from("|"), |row, cell| row + &cell + "|")
+        + "\n"
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Table {
+    pub heading: Vec<String>,
+    pub rows: Vec<Row>,
+    /// Where the heading starts
+    pub start: usize,
+    /// Where the next row goes, after the last row of the table
+    pub end: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Row {
+    pub cells: Vec<String>,
+    /// The byte range of the row in the document, including its line ending
+    pub span: Range<usize>,
+}
+
+impl Table {
+    /// Finds the first table in `contents` with a `column` heading
+    pub fn find(contents: &str, column: &str) -> Option<Self> {
+        let mut lines = lines(contents).peekable();
+
+        while let Some((heading_span, line)) = lines.next() {
+            let heading = cells(line);
+            let is_heading = heading.iter().any(|cell| cell == column)
+                && lines
+                    .peek()
+                    .is_some_and(|(_, divider)| is_divider(divider, heading.len()));
+            if !is_heading {
+                continue;
+            }
+
+            let (divider_span, _) = lines.next()?;
+            let rows = std::iter::from_fn(|| {
+                lines
+                    .next_if(|(_, line)| is_row(line))
+                    .map(|(span, line)| Row {
+                        cells: cells(line),
+                        span,
+                    })
+            })
+            .collect::<Vec<_>>();
+
+            return Some(Self {
+                start: heading_span.start,
+                end: rows.last().map_or(divider_span.end, |row| row.span.end),
+                heading,
+                rows,
+            });
+        }
+
+        None
+    }
+}
+
+/// Each line with its byte range, including the line ending
+fn lines(contents: &str) -> impl Iterator<Item = (Range<usize>, &str)> {
+    contents.split_inclusive('\n').scan(0, |start, line| {
+        let span = *start..*start + line.len();
+        *start = span.end;
+        Some((span, line.trim_end_matches(['\r', '\n'])))
+    })
+}
+
+fn is_row(line: &str) -> bool {
+    !line.trim().is_empty() && line.contains('|')
+}
+
+fn is_divider(line: &str, columns: usize) -> bool {
+    let cells = cells(line);
+    cells.len() == columns
+        && cells.iter().all(|cell| {
+            let dashes = cell.strip_prefix(':').unwrap_or(cell);
+            let dashes = dashes.strip_suffix(':').unwrap_or(dashes);
+            !dashes.is_empty() && dashes.chars().all(|char| char == '-')
+        })
+}
+
+fn cells(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut chars = line.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' if chars.peek() == Some(&'|') => cell.push(chars.next().unwrap_or('|')),
+            '|' => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(char),
+        }
+    }
+
+    // a trailing pipe closes the last cell rather than starting an empty one
+    if !cell.trim().is_empty() {
+        cells.push(cell.trim().to_string());
+    }
+
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::markdown::{Row, Table, row};
+
+    #[test]
+    fn row_escapes_pipes() {
+        assert_eq!("|a|b\\|c|\n", row(&["a", "b|c"]))
+    }
+
+    #[test]
+    fn finds_table_by_heading() {
+        let contents = "|name|version|\n|-|-|\n|anyhow|1.0.100|\n";
+
+        assert_eq!(
+            Some(Table {
+                heading: vec!["name".to_string(), "version".to_string()],
+                rows: vec![Row {
+                    cells: vec!["anyhow".to_string(), "1.0.100".to_string()],
+                    span: 21..38,
+                }],
+                start: 0,
+                end: 38,
+            }),
+            Table::find(contents, "name")
+        )
+    }
+
+    #[test]
+    fn finds_padded_and_aligned_table_after_other_content() {
+        let contents = "# Dependencies\n\n| other |\n|---|\n| x |\n\n| name   | version |\n|:-------|--------:|\n| anyhow | 1.0.100 |\n| syn    | 2.0.106 |\n\nTrailing prose.\n";
+
+        let table = Table::find(contents, "name").unwrap();
+
+        assert_eq!(vec!["name", "version"], table.heading);
+        assert_eq!(
+            vec![vec!["anyhow", "1.0.100"], vec!["syn", "2.0.106"]],
+            table
+                .rows
+                .iter()
+                .map(|row| row.cells.clone())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            "| syn    | 2.0.106 |\n",
+            &contents[table.rows[1].span.clone()]
+        );
+        assert_eq!(
+            "| name   | version |",
+            &contents[table.start..table.start + 20]
+        );
+        assert_eq!("\nTrailing prose.\n", &contents[table.end..])
+    }
+
+    #[test]
+    fn unescapes_pipes_in_cells() {
+        assert_eq!(
+            vec!["a|b", "c"],
+            Table::find("|name|other|\n|-|-|\n|a\\|b|c|\n", "name")
+                .unwrap()
+                .rows[0]
+                .cells
+        )
+    }
+
+    #[test]
+    fn tolerates_missing_outer_pipes_and_crlf() {
+        let table = Table::find(
+            "name | version\r\n--- | ---\r\nanyhow | 1.0.100\r\n",
+            "name",
+        )
+        .unwrap();
+
+        assert_eq!(vec!["anyhow", "1.0.100"], table.rows[0].cells);
+        assert_eq!(table.rows[0].span.end, table.end)
+    }
+
+    #[test]
+    fn heading_without_divider_is_not_a_table() {
+        assert_eq!(
+            None,
+            Table::find("|name|version|\n|anyhow|1.0.100|\n", "name")
+        )
+    }
+
+    #[test]
+    fn table_without_rows_ends_after_divider() {
+        let contents = "|name|version|\n|-|-|\n";
+
+        assert_eq!(contents.len(), Table::find(contents, "name").unwrap().end)
+    }
+}
@@ -0,0 +1,439 @@
+use crate::crates_io::{API_URL, canonical_name};
+use crate::http_client::{GetRequest, Response};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use reqwest::Url;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+// dump format - https://crates.io/data-access#database-dumps
+const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f%#z";
+
+/// The crates.io database dump, answering the crates.io api for the crates it was loaded with
+pub struct OfflineDatabase {
+    /// By name as crates.io compares them
+    crates: HashMap<String, CrateEntry>,
+}
+
+struct CrateEntry {
+    row: CrateRow,
+    downloads: u64,
+    versions: Vec<VersionRow>,
+    dependents: HashSet<u64>,
+}
+
+impl CrateEntry {
+    fn to_json(&self) -> anyhow::Result<serde_json::Value> {
+        let latest_version = self
+            .versions
+            .iter()
+            .filter(|version| version.yanked != "t")
+            .filter_map(|version| Some((stable_version(&version.num)?, &version.num)))
+            .max()
+            .map(|(_, num)| num);
+
+        Ok(serde_json::json!({
+            "name": self.row.name,
+            "max_stable_version": latest_version,
+            "downloads": self.downloads,
+            "num_versions": self.versions.len(),
+            "created_at": timestamp(&self.row.created_at)?,
+            "updated_at": timestamp(&self.row.updated_at)?,
+            "repository": self.row.repository,
+            "homepage": self.row.homepage,
+            "documentation": self.row.documentation,
+        }))
+    }
+}
+
+#[derive(Deserialize)]
+struct CrateRow {
+    id: u64,
+    name: String,
+    created_at: String,
+    updated_at: String,
+    /// Only in dumps before crate_downloads.csv
+    #[serde(default)]
+    downloads: Option<u64>,
+    repository: Option<String>,
+    homepage: Option<String>,
+    documentation: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CrateDownloadsRow {
+    crate_id: u64,
+    downloads: u64,
+}
+
+#[derive(Deserialize)]
+struct VersionRow {
+    crate_id: u64,
+    num: String,
+    yanked: String,
+}
+
+#[derive(Deserialize)]
+struct DefaultVersionRow {
+    crate_id: u64,
+    version_id: u64,
+}
+
+#[derive(Deserialize)]
+struct DependencyRow {
+    crate_id: u64,
+    version_id: u64,
+}
+
+/// The request needs the network, which is not used offline
+#[derive(Debug)]
+pub struct Offline {
+    pub url: String,
+}
+
+impl std::fmt::Display for Offline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "not available offline: {}", self.url)
+    }
+}
+
+impl std::error::Error for Offline {}
+
+impl OfflineDatabase {
+    /// Only the named crates are kept, the dump is too large to hold in memory
+    pub fn load(path: &Path, crate_names: &BTreeSet<String>) -> anyhow::Result<Self> {
+        let dump = Dump::open(path)?;
+        let wanted = crate_names
+            .iter()
+            .map(|name| canonical_name(name))
+            .collect::<HashSet<_>>();
+
+        let mut crates = HashMap::new();
+        dump.rows("crates.csv", |row: CrateRow| {
+            if wanted.contains(&canonical_name(&row.name)) {
+                crates.insert(
+                    row.id,
+                    CrateEntry {
+                        downloads: row.downloads.unwrap_or_default(),
+                        row,
+                        versions: Vec::new(),
+                        dependents: HashSet::new(),
+                    },
+                );
+            }
+        })?;
+
+        dump.optional_rows("crate_downloads.csv", |row: CrateDownloadsRow| {
+            if let Some(entry) = crates.get_mut(&row.crate_id) {
+                entry.downloads = row.downloads;
+            }
+        })?;
+
+        dump.rows("versions.csv", |row: VersionRow| {
+            if let Some(entry) = crates.get_mut(&row.crate_id) {
+                entry.versions.push(row);
+            }
+        })?;
+
+        // like the api, a crate depends on another when its default version does
+        let mut default_versions = HashMap::new();
+        dump.rows("default_versions.csv", |row: DefaultVersionRow| {
+            default_versions.insert(row.version_id, row.crate_id);
+        })?;
+
+        dump.rows("dependencies.csv", |row: DependencyRow| {
+            if let (Some(entry), Some(dependent)) = (
+                crates.get_mut(&row.crate_id),
+                default_versions.get(&row.version_id),
+            ) {
+                entry.dependents.insert(*dependent);
+            }
+        })?;
+
+        Ok(Self {
+            crates: crates
+                .into_values()
+                .map(|entry| (canonical_name(&entry.row.name), entry))
+                .collect(),
+        })
+    }
+
+    /// The same document as the api, so it is read the same way, crates not loaded are left out
+    fn crates(&self, url: &str) -> anyhow::Result<String> {
+        let url = Url::parse(url).with_context(|| format!("invalid url: {url}"))?;
+        let crates = url
+            .query_pairs()
+            .filter(|(name, _)| name == "ids[]")
+            .filter_map(|(_, crate_name)| self.crates.get(&canonical_name(&crate_name)))
+            .map(CrateEntry::to_json)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(serde_json::json!({
+            "crates": crates,
+            "meta": { "total": crates.len() },
+        })
+        .to_string())
+    }
+
+    fn reverse_dependencies(&self, crate_name: &str) -> anyhow::Result<String> {
+        let entry = self
+            .crates
+            .get(&canonical_name(crate_name))
+            .with_context(|| format!("{crate_name} is not in the offline database"))?;
+        Ok(serde_json::json!({ "meta": { "total": entry.dependents.len() } }).to_string())
+    }
+}
+
+impl GetRequest for OfflineDatabase {
+    fn get(&self, url: &str) -> Result<String, anyhow::Error> {
+        let offline = || Offline {
+            url: url.to_string(),
+        };
+        let path = url.strip_prefix(API_URL).ok_or_else(offline)?;
+        if path.starts_with('?') {
+            return self.crates(url);
+        }
+        match path.strip_prefix('/').and_then(|path| path.split_once('/')) {
+            Some((crate_name, "reverse_dependencies")) => self.reverse_dependencies(crate_name),
+            _ => Err(offline().into()),
+        }
+    }
+
+    fn get_response(&self, url: &str) -> Result<Response, anyhow::Error> {
+        Ok(Response {
+            body: self.get(url)?,
+            ..Default::default()
+        })
+    }
+}
+
+/// Prereleases are not stable, build metadata is ignored
+fn stable_version(num: &str) -> Option<[u64; 3]> {
+    let num = num.split_once('+').map_or(num, |(num, _)| num);
+    let mut parts = num.split('.').map(|part| part.parse().ok());
+    let version = [parts.next()??, parts.next()??, parts.next()??];
+    parts.next().is_none().then_some(version)
+}
+
+fn timestamp(timestamp: &str) -> anyhow::Result<DateTime<Utc>> {
+    DateTime::parse_from_str(timestamp, TIMESTAMP_FORMAT)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .with_context(|| format!("invalid timestamp in offline database: {timestamp}"))
+}
+
+/// The tables of an extracted dump, or of the tarball it is downloaded as
+enum Dump {
+    /// The dated directory the tarball extracts to
+    Directory(PathBuf),
+    Archive(PathBuf),
+}
+
+impl Dump {
+    fn open(path: &Path) -> anyhow::Result<Self> {
+        let metadata = std::fs::metadata(path)
+            .with_context(|| format!("failed to open offline database: {}", path.display()))?;
+        Ok(match metadata.is_dir() {
+            true => Self::Directory(path.to_path_buf()),
+            false => Self::Archive(path.to_path_buf()),
+        })
+    }
+
+    fn rows<T: DeserializeOwned>(&self, table: &str, each: impl FnMut(T)) -> anyhow::Result<()> {
+        match self.optional_rows(table, each)? {
+            true => Ok(()),
+            false => anyhow::bail!("offline database has no {table}"),
+        }
+    }
+
+    /// Whether the dump has the table, each row is read in turn
+    fn optional_rows<T: DeserializeOwned>(
+        &self,
+        table: &str,
+        each: impl FnMut(T),
+    ) -> anyhow::Result<bool> {
+        match self {
+            Self::Directory(path) => match File::open(path.join("data").join(table)) {
+                Ok(file) => read_rows(table, file, each).map(|_| true),
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(false),
+                Err(error) => Err(error).with_context(|| format!("failed to read {table}")),
+            },
+            // each table is a pass over the archive, entries can only be read in order
+            Self::Archive(path) => {
+                let file = File::open(path).context("failed to read offline database")?;
+                let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+                for entry in archive
+                    .entries()
+                    .context("failed to read offline database")?
+                {
+                    let entry = entry.context("failed to read offline database")?;
+                    if entry.path()?.ends_with(Path::new("data").join(table)) {
+                        return read_rows(table, entry, each).map(|_| true);
+                    }
+                }
+                Ok(false)
+            }
+        }
+    }
+}
+
+fn read_rows<T: DeserializeOwned>(
+    table: &str,
+    reader: impl Read,
+    mut each: impl FnMut(T),
+) -> anyhow::Result<()> {
+    for row in csv::Reader::from_reader(reader).deserialize() {
+        each(row.with_context(|| format!("failed to deserialize {table}"))?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crates_io::{Crate, get_crates};
+    use crate::fetched::Fetched;
+    use crate::http_client::GetRequest;
+    use crate::offline_db::{Offline, OfflineDatabase, stable_version};
+    use chrono::DateTime;
+    use std::collections::BTreeSet;
+    use std::path::{Path, PathBuf};
+    use std::str::FromStr;
+
+    const DUMP: &str = "tests/data/db-dump";
+
+    fn fetched_crates(http_client: &impl GetRequest, crate_names: &[&str]) -> Vec<Crate> {
+        get_crates(http_client, crate_names)
+            .unwrap()
+            .into_iter()
+            .map(|(_, crate_info)| crate_info.unwrap())
+            .collect()
+    }
+
+    fn load(path: &Path) -> OfflineDatabase {
+        OfflineDatabase::load(
+            path,
+            &BTreeSet::from([
+                "autospy".to_string(),
+                "anyhow".to_string(),
+                "no-repo".to_string(),
+            ]),
+        )
+        .unwrap()
+    }
+
+    fn archive(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("trust-list-{name}-{}.tar.gz", std::process::id()));
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            std::fs::File::create(&path).unwrap(),
+            flate2::Compression::fast(),
+        ));
+        builder.append_dir_all("2025-10-18-020000", DUMP).unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+        path
+    }
+
+    #[test]
+    fn crates_are_answered_from_the_dump() {
+        assert_eq!(
+            vec![Crate {
+                name: "autospy".to_string(),
+                version: String::new(),
+                latest_version: Some("0.0.10".to_string()),
+                license: None,
+                downloads: 1861,
+                contributors: Fetched::Absent,
+                reverse_dependencies: 0,
+                versions: 5,
+                created_at: DateTime::from_str("2025-05-15T18:21:04.123456Z").unwrap(),
+                updated_at: DateTime::from_str("2025-07-01T10:00:00Z").unwrap(),
+                repository: Fetched::Present("https://github.com/lhalf/autospy".to_string()),
+                fetched_at: Default::default(),
+            }],
+            fetched_crates(&load(Path::new(DUMP)), &["autospy"])
+        )
+    }
+
+    #[test]
+    fn reverse_dependencies_count_each_dependent_default_version_once() {
+        let database = load(Path::new(DUMP));
+
+        assert_eq!(
+            r#"{"meta":{"total":3}}"#,
+            database
+                .get("https://crates.io/api/v1/crates/anyhow/reverse_dependencies")
+                .unwrap()
+        )
+    }
+
+    #[test]
+    fn crate_without_repository_links_its_homepage() {
+        assert_eq!(
+            Fetched::Present("https://example.com/no_repo".to_string()),
+            fetched_crates(&load(Path::new(DUMP)), &["no-repo"])[0].repository
+        )
+    }
+
+    #[test]
+    fn crates_not_loaded_are_left_out() {
+        assert_eq!(
+            ["anyhow"],
+            fetched_crates(&load(Path::new(DUMP)), &["trust-list", "anyhow"])
+                .iter()
+                .map(|crate_info| crate_info.name.as_str())
+                .collect::<Vec<_>>()
+                .as_slice()
+        );
+        assert_eq!(
+            "trust-list is not in the offline database",
+            load(Path::new(DUMP))
+                .get("https://crates.io/api/v1/crates/trust-list/reverse_dependencies")
+                .unwrap_err()
+                .to_string()
+        )
+    }
+
+    #[test]
+    fn other_requests_are_not_available_offline() {
+        assert!(
+            load(Path::new(DUMP))
+                .get_response("https://api.github.com/repos/lhalf/autospy/contributors")
+                .unwrap_err()
+                .is::<Offline>()
+        )
+    }
+
+    #[test]
+    fn dump_tarball_is_read_like_a_directory() {
+        let path = archive("db-dump");
+        let database = load(&path);
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(
+            fetched_crates(&load(Path::new(DUMP)), &["anyhow"]),
+            fetched_crates(&database, &["anyhow"])
+        )
+    }
+
+    #[test]
+    fn missing_dump_fails_to_open() {
+        assert_eq!(
+            "failed to open offline database: tests/data/missing",
+            OfflineDatabase::load(Path::new("tests/data/missing"), &BTreeSet::new())
+                .err()
+                .unwrap()
+                .to_string()
+        )
+    }
+
+    #[test]
+    fn only_stable_versions_are_compared() {
+        assert_eq!(Some([0, 0, 10]), stable_version("0.0.10"));
+        assert_eq!(Some([1, 2, 3]), stable_version("1.2.3+build.5"));
+        assert_eq!(None, stable_version("0.1.0-alpha.1"));
+        assert_eq!(None, stable_version("1.2"))
+    }
+}
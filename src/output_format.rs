@@ -0,0 +1,1744 @@
+use crate::crates_io::Crate;
+use crate::file_io::FileIO;
+use crate::markdown::{self, Row, Table};
+use anyhow::Context;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputFormat {
+    #[default]
+    Markdown,
+    Json,
+    Csv,
+}
+
+/// A row of the output file by column, including any columns users added
+pub type Record = serde_json::Map<String, Value>;
+
+pub type ExistingCrates = BTreeMap<(String, String), Record>;
+
+/// The output file has no column of the name, such as the column to sort by
+#[derive(Debug, PartialEq)]
+pub struct MissingColumn(pub String);
+
+impl std::fmt::Display for MissingColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "output file has no {} column", self.0)
+    }
+}
+
+impl std::error::Error for MissingColumn {}
+
+impl OutputFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Json => "json",
+            Self::Csv => "csv",
+        }
+    }
+
+    /// The contents of an output file without entries, with the given columns
+    pub fn heading<T: AsRef<str>>(self, columns: &[T]) -> String {
+        match self {
+            Self::Markdown => [
+                markdown::row(columns),
+                markdown::row(&vec!["-"; columns.len()]),
+            ]
+            .join(""),
+            Self::Json => "[]\n".to_string(),
+            Self::Csv => csv_record(columns),
+        }
+    }
+
+    /// The record of each crate already in the output file by name and version
+    pub fn existing_crates(self, contents: &str) -> anyhow::Result<ExistingCrates> {
+        match self {
+            Self::Markdown => Ok(Table::find(contents, "name")
+                .map(|table| {
+                    table
+                        .rows
+                        .iter()
+                        .filter_map(|row| keyed_record(&table.heading, &row.cells))
+                        .collect()
+                })
+                .unwrap_or_default()),
+            Self::Json => Ok(parse_json_document(contents)?
+                .into_iter()
+                .filter_map(|entry| match entry {
+                    Value::Object(record) => {
+                        let (name, version) = json_crate(&record)?;
+                        Some(((name.to_string(), version.to_string()), record))
+                    }
+                    _ => None,
+                })
+                .collect()),
+            Self::Csv => {
+                let mut records = parse_csv(contents).into_iter();
+                let heading = records.next().unwrap_or_default();
+                Ok(records
+                    .filter_map(|record| keyed_record(&heading, &record))
+                    .collect())
+            }
+        }
+    }
+
+    /// Empties the output file contents, keeping the columns users added to the table after the generated `columns`
+    pub fn recreate(self, contents: &str, columns: &[String]) -> String {
+        let user_columns = |heading: &[String]| {
+            heading
+                .iter()
+                .filter(|column| !Crate::columns().contains(&column.as_str()))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        match self {
+            Self::Markdown => match Table::find(contents, "name") {
+                Some(table) => {
+                    let heading = [columns.to_vec(), user_columns(&table.heading)].concat();
+                    [
+                        &contents[..table.start],
+                        &markdown::row(&heading),
+                        &markdown::row(&vec!["-"; heading.len()]),
+                        &contents[table.end..],
+                    ]
+                    .join("")
+                }
+                None => self.heading(columns),
+            },
+            Self::Json => self.heading(columns),
+            Self::Csv => csv_record(
+                &[
+                    columns.to_vec(),
+                    user_columns(&parse_csv(contents).into_iter().next().unwrap_or_default()),
+                ]
+                .concat(),
+            ),
+        }
+    }
+
+    /// Adds an entry to the output file, replacing the entry for the same crate version if there is one,
+    /// `contents` tracks what has been written so far and `existing` keeps the cells of columns users added,
+    /// tables are laid out by their heading while json entries only have the generated `columns`
+    pub fn write_entry(
+        self,
+        output_file: &impl FileIO,
+        contents: &mut String,
+        crate_info: &Crate,
+        existing: Option<&Record>,
+        columns: &[String],
+    ) -> anyhow::Result<()> {
+        self.put_entry(
+            output_file,
+            contents,
+            (&crate_info.name, &crate_info.version),
+            |heading| match (self, heading) {
+                (Self::Markdown, Some(heading)) => {
+                    markdown::row(&cells(heading, crate_info.table_cells(), existing))
+                }
+                (Self::Markdown, None) => crate_info.table_entry(),
+                (_, Some(heading)) => csv_record(&cells(heading, crate_info.csv_cells(), existing)),
+                (_, None) => crate_info.csv_entry(),
+            },
+            || {
+                let mut generated = match serde_json::to_value(crate_info)
+                    .context("failed to serialize crate info")?
+                {
+                    Value::Object(generated) => generated,
+                    _ => Record::new(),
+                };
+                let mut entry = columns
+                    .iter()
+                    .filter_map(|column| Some((column.clone(), generated.remove(column)?)))
+                    .collect::<Record>();
+                if let Some(existing) = existing {
+                    existing
+                        .iter()
+                        .filter(|(key, _)| !Crate::columns().contains(&key.as_str()))
+                        .for_each(|(key, value)| {
+                            entry.insert(key.clone(), value.clone());
+                        });
+                }
+                Ok(entry)
+            },
+        )
+    }
+
+    /// Adds an existing record back as it was, such as one of a crate that could not be fetched again
+    pub fn restore_entry(
+        self,
+        output_file: &impl FileIO,
+        contents: &mut String,
+        (name, version): (&str, &str),
+        record: &Record,
+    ) -> anyhow::Result<()> {
+        self.put_entry(
+            output_file,
+            contents,
+            (name, version),
+            |heading| {
+                let cells = heading
+                    .map_or_else(
+                        || {
+                            Crate::columns()
+                                .iter()
+                                .map(|column| column.to_string())
+                                .collect()
+                        },
+                        <[String]>::to_vec,
+                    )
+                    .iter()
+                    .map(|column| match record.get(column) {
+                        Some(Value::String(cell)) => cell.clone(),
+                        Some(Value::Null) | None => String::new(),
+                        Some(cell) => cell.to_string(),
+                    })
+                    .collect::<Vec<_>>();
+                match self {
+                    Self::Markdown => markdown::row(&cells),
+                    Self::Csv | Self::Json => csv_record(&cells),
+                }
+            },
+            || Ok(record.clone()),
+        )
+    }
+
+    /// Adds the entry for the crate version or replaces it, `row` lays the table row out by the heading if there is one
+    fn put_entry(
+        self,
+        output_file: &impl FileIO,
+        contents: &mut String,
+        (crate_name, crate_version): (&str, &str),
+        row: impl Fn(Option<&[String]>) -> String,
+        json_entry: impl FnOnce() -> anyhow::Result<Record>,
+    ) -> anyhow::Result<()> {
+        let is_entry =
+            |name: &str, version: &str| is_crate(name, version, crate_name, crate_version);
+
+        match self {
+            Self::Markdown => {
+                let table = Table::find(contents, "name");
+                let entry = row(table.as_ref().map(|table| table.heading.as_slice()));
+                let replaced = table.as_ref().and_then(|table| {
+                    table.rows.iter().find(|row| {
+                        markdown_crate(table, row)
+                            .is_some_and(|(name, version)| is_entry(name, version))
+                    })
+                });
+                match (replaced, table.as_ref().map(|table| table.end)) {
+                    (Some(row), _) => {
+                        contents.replace_range(row.span.clone(), &entry);
+                        output_file.write(contents.as_bytes())?;
+                    }
+                    // content after the table is kept after it
+                    (None, Some(end)) if end < contents.len() => {
+                        contents.insert_str(end, &entry);
+                        output_file.write(contents.as_bytes())?;
+                    }
+                    // the last row of a file may not end with a newline
+                    (None, _) => match contents.is_empty() || contents.ends_with('\n') {
+                        true => append_entry(output_file, contents, entry)?,
+                        false => append_entry(output_file, contents, format!("\n{entry}"))?,
+                    },
+                }
+            }
+            Self::Csv => {
+                let records = parse_csv(contents);
+                let heading = records.first().cloned().unwrap_or_default();
+                let entry = row((!heading.is_empty()).then_some(heading.as_slice()));
+                match records.iter().skip(1).position(|record| {
+                    csv_crate(&heading, record)
+                        .is_some_and(|(name, version)| is_entry(name, version))
+                }) {
+                    Some(index) => {
+                        *contents = records
+                            .iter()
+                            .enumerate()
+                            .map(|(record_index, record)| match record_index == index + 1 {
+                                true => entry.clone(),
+                                false => csv_record(record),
+                            })
+                            .collect();
+                        output_file.write(contents.as_bytes())?;
+                    }
+                    // the last record may not end with a line ending
+                    None => match contents.is_empty() || contents.ends_with('\n') {
+                        true => append_entry(output_file, contents, entry)?,
+                        false => append_entry(output_file, contents, format!("\r\n{entry}"))?,
+                    },
+                }
+            }
+            Self::Json => {
+                let mut document = parse_json_document(contents)?;
+                let entry = json_entry()?;
+                match document.iter_mut().find(|replaced| {
+                    replaced
+                        .as_object()
+                        .and_then(json_crate)
+                        .is_some_and(|(name, version)| is_entry(name, version))
+                }) {
+                    Some(replaced) => *replaced = Value::Object(entry),
+                    None => document.push(Value::Object(entry)),
+                }
+                *contents = serde_json::to_string_pretty(&document)
+                    .context("failed to serialize json document")?
+                    + "\n";
+                output_file.write(contents.as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Removes the entries `keep` rejects from the output file, returns the name and version of each removed crate
+    pub fn remove_entries(
+        self,
+        output_file: &impl FileIO,
+        contents: &mut String,
+        keep: impl Fn(&str, &str) -> bool,
+    ) -> anyhow::Result<Vec<(String, String)>> {
+        let mut removed = Vec::new();
+        let mut is_removed = |name: &str, version: &str| {
+            let is_removed = !keep(name, version);
+            if is_removed {
+                removed.push((name.to_string(), version.to_string()));
+            }
+            is_removed
+        };
+
+        let pruned = match self {
+            Self::Markdown => {
+                let mut pruned = contents.clone();
+                if let Some(table) = Table::find(contents, "name") {
+                    let spans = table
+                        .rows
+                        .iter()
+                        .filter(|row| {
+                            markdown_crate(&table, row)
+                                .is_some_and(|(name, version)| is_removed(name, version))
+                        })
+                        .map(|row| row.span.clone())
+                        .collect::<Vec<_>>();
+                    spans
+                        .into_iter()
+                        .rev()
+                        .for_each(|span| pruned.replace_range(span, ""));
+                }
+                pruned
+            }
+            Self::Csv => {
+                let records = parse_csv(contents);
+                let heading = records.first().cloned().unwrap_or_default();
+                records
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(index, record)| {
+                        *index < 1
+                            || !csv_crate(&heading, record)
+                                .is_some_and(|(name, version)| is_removed(name, version))
+                    })
+                    .map(|(_, record)| csv_record(&record))
+                    .collect()
+            }
+            Self::Json => {
+                let mut document = parse_json_document(contents)?;
+                document.retain(|entry| {
+                    !entry
+                        .as_object()
+                        .and_then(json_crate)
+                        .is_some_and(|(name, version)| is_removed(name, version))
+                });
+                serde_json::to_string_pretty(&document)
+                    .context("failed to serialize json document")?
+                    + "\n"
+            }
+        };
+
+        if !removed.is_empty() {
+            *contents = pruned;
+            output_file.write(contents.as_bytes())?;
+        }
+
+        Ok(removed)
+    }
+
+    /// Fails unless entries written to the output file will have `column`, tables have the columns of their heading
+    /// while json entries have the generated `columns` and any their existing entries have
+    pub fn check_column(
+        self,
+        contents: &str,
+        columns: &[String],
+        column: &str,
+    ) -> Result<(), MissingColumn> {
+        let has_column = match self {
+            Self::Markdown => Table::find(contents, "name").map_or_else(
+                || columns.iter().any(|generated| generated == column),
+                |table| table.heading.iter().any(|heading| heading == column),
+            ),
+            Self::Csv => match parse_csv(contents).into_iter().next() {
+                Some(heading) => heading.iter().any(|heading| heading == column),
+                None => columns.iter().any(|generated| generated == column),
+            },
+            Self::Json => {
+                columns.iter().any(|generated| generated == column)
+                    || parse_json_document(contents)
+                        .unwrap_or_default()
+                        .iter()
+                        .any(|entry| entry.get(column).is_some())
+            }
+        };
+        match has_column {
+            true => Ok(()),
+            false => Err(MissingColumn(column.to_string())),
+        }
+    }
+
+    /// Sorts every entry of the output file by the cells of `column`, entries with equal cells keep their order
+    pub fn sort_entries(
+        self,
+        output_file: &impl FileIO,
+        contents: &mut String,
+        column: &str,
+        descending: bool,
+    ) -> anyhow::Result<()> {
+        let missing_column = || MissingColumn(column.to_string());
+        let sort = |keys: Vec<SortKey>| {
+            let mut order = (0..keys.len()).collect::<Vec<_>>();
+            order.sort_by(|a, b| match (&keys[*a], &keys[*b]) {
+                // empty cells are last in either direction
+                (SortKey::Empty, SortKey::Empty) => Ordering::Equal,
+                (SortKey::Empty, _) => Ordering::Greater,
+                (_, SortKey::Empty) => Ordering::Less,
+                (a, b) => {
+                    let ordering = a.partial_cmp(b).unwrap_or(Ordering::Equal);
+                    match descending {
+                        true => ordering.reverse(),
+                        false => ordering,
+                    }
+                }
+            });
+            order
+        };
+
+        let sorted = match self {
+            Self::Markdown => {
+                let Some(table) = Table::find(contents, "name") else {
+                    return Ok(());
+                };
+                let index = table
+                    .heading
+                    .iter()
+                    .position(|heading| heading == column)
+                    .ok_or_else(missing_column)?;
+                let Some(first) = table.rows.first() else {
+                    return Ok(());
+                };
+                let rows = sort(
+                    table
+                        .rows
+                        .iter()
+                        .map(|row| {
+                            sort_key(column, row.cells.get(index).map_or("", String::as_str))
+                        })
+                        .collect(),
+                )
+                .into_iter()
+                .map(|row| {
+                    let row = &contents[table.rows[row].span.clone()];
+                    // the last row of a file may not end with a newline
+                    match row.ends_with('\n') {
+                        true => row.to_string(),
+                        false => format!("{row}\n"),
+                    }
+                })
+                .collect::<String>();
+                [&contents[..first.span.start], &rows, &contents[table.end..]].join("")
+            }
+            Self::Csv => {
+                let mut records = parse_csv(contents).into_iter();
+                let Some(heading) = records.next() else {
+                    return Ok(());
+                };
+                let index = heading
+                    .iter()
+                    .position(|heading| heading == column)
+                    .ok_or_else(missing_column)?;
+                let records = records.collect::<Vec<_>>();
+                std::iter::once(csv_record(&heading))
+                    .chain(
+                        sort(
+                            records
+                                .iter()
+                                .map(|record| {
+                                    sort_key(column, record.get(index).map_or("", String::as_str))
+                                })
+                                .collect(),
+                        )
+                        .into_iter()
+                        .map(|record| csv_record(&records[record])),
+                    )
+                    .collect()
+            }
+            Self::Json => {
+                let document = parse_json_document(contents)?;
+                if !document.is_empty() && document.iter().all(|entry| entry.get(column).is_none())
+                {
+                    return Err(missing_column().into());
+                }
+                let keys = document
+                    .iter()
+                    .map(|entry| match entry.get(column) {
+                        Some(Value::String(cell)) => sort_key(column, cell),
+                        Some(Value::Number(number)) => {
+                            SortKey::Number(number.as_f64().unwrap_or_default())
+                        }
+                        Some(Value::Array(values)) => SortKey::Number(values.len() as f64),
+                        Some(Value::Null) | None => SortKey::Empty,
+                        Some(value) => SortKey::Text(value.to_string()),
+                    })
+                    .collect();
+                let sorted = sort(keys)
+                    .into_iter()
+                    .map(|entry| document[entry].clone())
+                    .collect::<Vec<_>>();
+                serde_json::to_string_pretty(&sorted)
+                    .context("failed to serialize json document")?
+                    + "\n"
+            }
+        };
+
+        if sorted != *contents {
+            *contents = sorted;
+            output_file.write(contents.as_bytes())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The generated columns to write in order, all of them unless selected,
+/// name and version are required as they identify the crate of each existing row
+pub fn select_columns(selected: Option<Vec<String>>) -> anyhow::Result<Vec<String>> {
+    let Some(selected) = selected else {
+        return Ok(Crate::columns()
+            .iter()
+            .map(|column| column.to_string())
+            .collect());
+    };
+    let selected = selected
+        .iter()
+        .map(|column| column.trim().to_string())
+        .collect::<Vec<_>>();
+
+    for (index, column) in selected.iter().enumerate() {
+        if !Crate::columns().contains(&column.as_str()) {
+            anyhow::bail!(
+                "unknown column: {column}, expected one of {}",
+                Crate::columns().join(", ")
+            )
+        }
+        if selected[..index].contains(column) {
+            anyhow::bail!("duplicate column: {column}")
+        }
+    }
+    for required in ["name", "version"] {
+        if !selected.iter().any(|column| column == required) {
+            anyhow::bail!("columns must include {required}")
+        }
+    }
+
+    Ok(selected)
+}
+
+/// When the record was last fetched, rows written before this was recorded have none
+pub fn fetched_at(record: &Record) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(record.get("fetched_at")?.as_str()?)
+        .ok()
+        .map(|fetched_at| fetched_at.to_utc())
+}
+
+/// Numbers sort before dates and dates before text, so a column of one kind sorts by value
+#[derive(PartialEq, PartialOrd)]
+enum SortKey {
+    Number(f64),
+    Date(DateTime<Utc>),
+    /// The numeric components, whether it is a release and the pre-release, so pre-releases sort first
+    Version(Vec<u64>, bool, String),
+    Text(String),
+    Empty,
+}
+
+fn sort_key(column: &str, cell: &str) -> SortKey {
+    let cell = cell.trim();
+    if let Some(version) = ["version", "latest_version"]
+        .contains(&column)
+        .then(|| version_key(cell))
+        .flatten()
+    {
+        return version;
+    }
+    // tables written before contributors were counted exactly cap them at 30+
+    if let Ok(number) = cell.trim_end_matches('+').parse::<f64>() {
+        return SortKey::Number(number);
+    }
+    if let Ok(date) = DateTime::parse_from_rfc3339(cell) {
+        return SortKey::Date(date.to_utc());
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(cell, "%d/%m/%Y")
+        .or_else(|_| NaiveDate::parse_from_str(cell, "%Y-%m-%d"))
+    {
+        return SortKey::Date(date.and_time(NaiveTime::MIN).and_utc());
+    }
+    // unknown values sort with empty cells rather than as text
+    match cell {
+        "" | "n/a" | "?" => SortKey::Empty,
+        _ => SortKey::Text(cell.to_string()),
+    }
+}
+
+// semver - https://semver.org/#spec-item-11, build metadata is ignored
+fn version_key(cell: &str) -> Option<SortKey> {
+    let version = cell.split('+').next()?;
+    let (numbers, pre_release) = version.split_once('-').unwrap_or((version, ""));
+    let numbers = numbers
+        .split('.')
+        .map(|number| number.parse::<u64>().ok())
+        .collect::<Option<Vec<_>>>()?;
+    Some(SortKey::Version(
+        numbers,
+        pre_release.is_empty(),
+        pre_release.to_string(),
+    ))
+}
+
+fn append_entry(
+    output_file: &impl FileIO,
+    contents: &mut String,
+    entry: String,
+) -> anyhow::Result<()> {
+    output_file.append(entry.as_bytes())?;
+    contents.push_str(&entry);
+    Ok(())
+}
+
+/// Lays the generated cells out by the heading, columns users added keep their existing cells
+fn cells(heading: &[String], generated: Vec<String>, existing: Option<&Record>) -> Vec<String> {
+    heading
+        .iter()
+        .map(
+            |column| match Crate::columns().iter().position(|field| field == column) {
+                Some(field) => generated[field].clone(),
+                None => existing
+                    .and_then(|record| record.get(column))
+                    .map(|cell| match cell {
+                        Value::String(cell) => cell.clone(),
+                        cell => cell.to_string(),
+                    })
+                    .unwrap_or_default(),
+            },
+        )
+        .collect()
+}
+
+/// Whether the entry `name` and `version` is for the crate version, entries without a version,
+/// such as those of tables written before versions were recorded, are for every version of the crate
+pub fn is_crate(name: &str, version: &str, crate_name: &str, crate_version: &str) -> bool {
+    name == crate_name && (version.is_empty() || version == crate_version)
+}
+
+fn keyed_record(heading: &[String], cells: &[String]) -> Option<((String, String), Record)> {
+    let (name, version) = csv_crate(heading, cells)?;
+    let key = (name.to_string(), version.to_string());
+    let record = heading
+        .iter()
+        .zip(cells)
+        .map(|(column, cell)| (column.clone(), Value::String(cell.clone())))
+        .collect::<Record>();
+    Some((key, record))
+}
+
+fn markdown_crate<'a>(table: &Table, row: &'a Row) -> Option<(&'a str, &'a str)> {
+    csv_crate(&table.heading, &row.cells)
+}
+
+fn csv_crate<'a>(heading: &[String], record: &'a [String]) -> Option<(&'a str, &'a str)> {
+    let column = |name: &str| heading.iter().position(|column| column == name);
+    let name = record.get(column("name")?)?;
+    let version = column("version")
+        .and_then(|version| record.get(version))
+        .map_or("", String::as_str);
+    (!name.is_empty()).then_some((name, version))
+}
+
+fn json_crate(record: &Record) -> Option<(&str, &str)> {
+    let name = record.get("name")?.as_str()?;
+    let version = record
+        .get("version")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    Some((name, version))
+}
+
+/// A csv record with crlf line ending, fields are quoted only when needed
+pub fn csv_record<T: AsRef<str>>(fields: &[T]) -> String {
+    let mut writer = csv::WriterBuilder::new()
+        .terminator(csv::Terminator::CRLF)
+        .from_writer(Vec::new());
+    // writing to a vec can't fail and the fields are utf-8
+    let _ = writer.write_record(fields.iter().map(AsRef::as_ref));
+    String::from_utf8(writer.into_inner().unwrap_or_default()).unwrap_or_default()
+}
+
+/// Every csv record, records can have any number of fields
+fn parse_csv(contents: &str) -> Vec<Vec<String>> {
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(contents.as_bytes())
+        .records()
+        // the contents are utf-8 and records can have any length, so reading can't fail
+        .flatten()
+        .map(|record| record.iter().map(str::to_string).collect())
+        .collect()
+}
+
+fn parse_json_document(contents: &str) -> anyhow::Result<Vec<Value>> {
+    if contents.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    serde_json::from_str(contents).context("output file is not a json array")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crates_io::Crate;
+    use crate::fetched::Fetched;
+    use crate::file_io::FileIOSpy;
+    use crate::markdown;
+    use crate::output_format::{
+        ExistingCrates, MissingColumn, OutputFormat, csv_record, fetched_at, parse_csv,
+        select_columns,
+    };
+    use chrono::{DateTime, Utc};
+    use serde_json::Value;
+    use std::collections::BTreeMap;
+    use std::str::FromStr;
+
+    fn example_crate() -> Crate {
+        Crate {
+            name: "example".to_string(),
+            version: "1.0.0".to_string(),
+            latest_version: Some("1.2.0".to_string()),
+            license: Some("MIT OR Apache-2.0".to_string()),
+            downloads: 100,
+            contributors: Fetched::Present(40),
+            reverse_dependencies: 10,
+            versions: 2,
+            created_at: Default::default(),
+            updated_at: Default::default(),
+            repository: Fetched::Present("https://github.com/lhalf/trust-list".to_string()),
+            fetched_at: Default::default(),
+        }
+    }
+
+    fn all_columns() -> Vec<String> {
+        select_columns(None).unwrap()
+    }
+
+    fn fetched(existing: ExistingCrates) -> BTreeMap<(String, String), Option<DateTime<Utc>>> {
+        existing
+            .into_iter()
+            .map(|(key, record)| (key, fetched_at(&record)))
+            .collect()
+    }
+
+    #[test]
+    fn markdown_existing_crates() {
+        assert_eq!(
+            BTreeMap::from([
+                (("anyhow".to_string(), "1.0.100".to_string()), None),
+                (("syn".to_string(), "1.0.109".to_string()), None),
+                (("syn".to_string(), "2.0.106".to_string()), None),
+            ]),
+            fetched(OutputFormat::Markdown
+                .existing_crates(
+                    "|name|version|\n|-|-|\n|anyhow|1.0.100|\n| syn |1.0.109|\n|syn| 2.0.106 |\n"
+                )
+                .unwrap())
+        )
+    }
+
+    #[test]
+    fn json_existing_crates() {
+        assert_eq!(
+            BTreeMap::from([
+                (("syn".to_string(), "1.0.109".to_string()), None),
+                (("syn".to_string(), "2.0.106".to_string()), None),
+            ]),
+            fetched(OutputFormat::Json
+                .existing_crates(
+                    r#"[{"name": "syn", "version": "1.0.109"}, {"name": "syn", "version": "2.0.106"}]"#
+                )
+                .unwrap())
+        )
+    }
+
+    #[test]
+    fn json_existing_crates_from_empty_file() {
+        assert!(fetched(OutputFormat::Json.existing_crates("").unwrap()).is_empty())
+    }
+
+    #[test]
+    fn json_existing_crates_from_invalid_document() {
+        assert_eq!(
+            "output file is not a json array",
+            OutputFormat::Json
+                .existing_crates("|name|downloads|")
+                .unwrap_err()
+                .to_string()
+        )
+    }
+
+    #[test]
+    fn json_entry_is_written_with_typed_values() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents = OutputFormat::Json.heading(Crate::columns());
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        OutputFormat::Json
+            .write_entry(
+                &file_io_spy,
+                &mut contents,
+                &example_crate(),
+                None,
+                &all_columns(),
+            )
+            .unwrap();
+
+        let expected = r#"[
+  {
+    "name": "example",
+    "version": "1.0.0",
+    "latest_version": "1.2.0",
+    "license": "MIT OR Apache-2.0",
+    "downloads": 100,
+    "contributors": 40,
+    "reverse_dependencies": 10,
+    "versions": 2,
+    "created_at": "1970-01-01T00:00:00Z",
+    "updated_at": "1970-01-01T00:00:00Z",
+    "repository": "https://github.com/lhalf/trust-list",
+    "fetched_at": "1970-01-01T00:00:00Z"
+  }
+]
+"#;
+        assert_eq!(expected, contents);
+        assert_eq!([expected.as_bytes().to_vec()], file_io_spy.write.arguments)
+    }
+
+    #[test]
+    fn json_entry_keeps_existing_entries() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents = r#"[{"name": "anyhow", "version": "1.0.100"}]"#.to_string();
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        OutputFormat::Json
+            .write_entry(
+                &file_io_spy,
+                &mut contents,
+                &example_crate(),
+                None,
+                &all_columns(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            BTreeMap::from([
+                (("anyhow".to_string(), "1.0.100".to_string()), None),
+                (
+                    ("example".to_string(), "1.0.0".to_string()),
+                    Some(Default::default())
+                ),
+            ]),
+            fetched(OutputFormat::Json.existing_crates(&contents).unwrap())
+        )
+    }
+
+    #[test]
+    fn markdown_entry_is_appended() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents = OutputFormat::Markdown.heading(Crate::columns());
+
+        file_io_spy.append.returns.set([Ok(())]);
+
+        OutputFormat::Markdown
+            .write_entry(
+                &file_io_spy,
+                &mut contents,
+                &example_crate(),
+                None,
+                &all_columns(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            [b"|example|1.0.0|1.2.0|MIT OR Apache-2.0|100|40|10|2|01/01/1970|01/01/1970|https://github.com/lhalf/trust-list|1970-01-01T00:00:00Z|\n"
+                .to_vec()],
+            file_io_spy.append.arguments
+        )
+    }
+
+    #[test]
+    fn markdown_entry_is_appended_after_row_without_newline() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents = "|name|version|\n|-|-|\n|zzz|1.0.0|".to_string();
+
+        file_io_spy.append.returns.set([Ok(())]);
+
+        OutputFormat::Markdown
+            .write_entry(
+                &file_io_spy,
+                &mut contents,
+                &example_crate(),
+                None,
+                &all_columns(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            "|name|version|\n|-|-|\n|zzz|1.0.0|\n|example|1.0.0|\n",
+            contents
+        )
+    }
+
+    #[test]
+    fn csv_existing_crates() {
+        assert_eq!(
+            BTreeMap::from([
+                (("anyhow".to_string(), "1.0.100".to_string()), None),
+                (("clap".to_string(), "4.5.51".to_string()), None),
+            ]),
+            fetched(
+                OutputFormat::Csv
+                    .existing_crates("name,version\r\nanyhow,1.0.100\r\n\"clap\",4.5.51\r\n")
+                    .unwrap()
+            )
+        )
+    }
+
+    #[test]
+    fn csv_existing_crates_from_heading_only() {
+        assert!(
+            fetched(
+                OutputFormat::Csv
+                    .existing_crates(&OutputFormat::Csv.heading(Crate::columns()))
+                    .unwrap()
+            )
+            .is_empty()
+        )
+    }
+
+    #[test]
+    fn csv_entry_is_appended() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents = OutputFormat::Csv.heading(Crate::columns());
+
+        file_io_spy.append.returns.set([Ok(())]);
+
+        OutputFormat::Csv
+            .write_entry(
+                &file_io_spy,
+                &mut contents,
+                &example_crate(),
+                None,
+                &all_columns(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            [b"example,1.0.0,1.2.0,MIT OR Apache-2.0,100,40,10,2,1970-01-01,1970-01-01,https://github.com/lhalf/trust-list,1970-01-01T00:00:00Z\r\n"
+                .to_vec()],
+            file_io_spy.append.arguments
+        )
+    }
+
+    #[test]
+    fn csv_entry_is_appended_after_record_without_line_ending() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents = "name,version\r\nzzz,1.0.0".to_string();
+
+        file_io_spy.append.returns.set([Ok(())]);
+
+        OutputFormat::Csv
+            .write_entry(
+                &file_io_spy,
+                &mut contents,
+                &example_crate(),
+                None,
+                &all_columns(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            vec![
+                vec!["name", "version"],
+                vec!["zzz", "1.0.0"],
+                vec!["example", "1.0.0"]
+            ],
+            parse_csv(&contents)
+        )
+    }
+
+    #[test]
+    fn markdown_existing_crates_with_fetched_at() {
+        assert_eq!(
+            BTreeMap::from([
+                (
+                    ("anyhow".to_string(), "1.0.100".to_string()),
+                    Some(DateTime::from_str("2025-10-01T12:00:00Z").unwrap())
+                ),
+                (("syn".to_string(), "2.0.106".to_string()), None),
+            ]),
+            fetched(OutputFormat::Markdown
+                .existing_crates(
+                    "|name|version|fetched_at|\n|-|-|-|\n|anyhow|1.0.100|2025-10-01T12:00:00Z|\n|syn|2.0.106|invalid|\n"
+                )
+                .unwrap())
+        )
+    }
+
+    #[test]
+    fn csv_existing_crates_with_fetched_at() {
+        assert_eq!(
+            BTreeMap::from([(
+                ("anyhow".to_string(), "1.0.100".to_string()),
+                Some(DateTime::from_str("2025-10-01T12:00:00Z").unwrap())
+            )]),
+            fetched(
+                OutputFormat::Csv
+                    .existing_crates(
+                        "name,version,fetched_at\r\nanyhow,1.0.100,2025-10-01T12:00:00Z\r\n"
+                    )
+                    .unwrap()
+            )
+        )
+    }
+
+    #[test]
+    fn markdown_entry_replaces_existing_row() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents = [
+            OutputFormat::Markdown.heading(Crate::columns()),
+            "|anyhow|1.0.100|\n|example|1.0.0|old|\n|example|2.0.0|\n".to_string(),
+        ]
+        .join("");
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        OutputFormat::Markdown
+            .write_entry(
+                &file_io_spy,
+                &mut contents,
+                &example_crate(),
+                None,
+                &all_columns(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            [
+                OutputFormat::Markdown.heading(Crate::columns()),
+                "|anyhow|1.0.100|\n".to_string(),
+                example_crate().table_entry(),
+                "|example|2.0.0|\n".to_string()
+            ]
+            .join(""),
+            contents
+        );
+        assert_eq!([contents.as_bytes().to_vec()], file_io_spy.write.arguments)
+    }
+
+    #[test]
+    fn plain_csv_fields_are_not_quoted() {
+        assert_eq!("name,100\r\n", csv_record(&["name", "100"]))
+    }
+
+    #[test]
+    fn csv_fields_with_special_characters_are_quoted() {
+        assert_eq!(
+            "\"a,b\",\"say \"\"hi\"\"\",\"multi\nline\"\r\n",
+            csv_record(&["a,b", "say \"hi\"", "multi\nline"])
+        )
+    }
+
+    #[test]
+    fn parses_plain_csv_records() {
+        assert_eq!(
+            vec![vec!["name", "downloads"], vec!["anyhow", "10"]],
+            parse_csv("name,downloads\r\nanyhow,10\r\n")
+        )
+    }
+
+    #[test]
+    fn parses_quoted_csv_records() {
+        assert_eq!(
+            vec![vec!["a,b", "say \"hi\"", "multi\nline"]],
+            parse_csv("\"a,b\",\"say \"\"hi\"\"\",\"multi\nline\"\r\n")
+        )
+    }
+
+    #[test]
+    fn parses_final_csv_record_without_line_ending() {
+        assert_eq!(vec![vec!["anyhow", ""]], parse_csv("anyhow,"))
+    }
+
+    #[test]
+    fn csv_entry_replaces_existing_record() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents = [
+            OutputFormat::Csv.heading(Crate::columns()),
+            "example,1.0.0,old\r\n\"anyhow\",1.0.100\r\n".to_string(),
+        ]
+        .join("");
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        OutputFormat::Csv
+            .write_entry(
+                &file_io_spy,
+                &mut contents,
+                &example_crate(),
+                None,
+                &all_columns(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            [
+                OutputFormat::Csv.heading(Crate::columns()),
+                example_crate().csv_entry(),
+                "anyhow,1.0.100\r\n".to_string()
+            ]
+            .join(""),
+            contents
+        );
+        assert_eq!([contents.as_bytes().to_vec()], file_io_spy.write.arguments)
+    }
+
+    #[test]
+    fn json_entry_replaces_existing_entry() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents =
+            r#"[{"name": "example", "version": "1.0.0", "downloads": 1}]"#.to_string();
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        OutputFormat::Json
+            .write_entry(
+                &file_io_spy,
+                &mut contents,
+                &example_crate(),
+                None,
+                &all_columns(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            serde_json::json!([example_crate()]),
+            serde_json::from_str::<serde_json::Value>(&contents).unwrap()
+        )
+    }
+
+    #[test]
+    fn markdown_entries_not_kept_are_removed() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents = [
+            OutputFormat::Markdown.heading(Crate::columns()),
+            "|anyhow|1.0.100|\n|example|1.0.0|\n|example|2.0.0|\n".to_string(),
+        ]
+        .join("");
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        assert_eq!(
+            [("example".to_string(), "1.0.0".to_string())],
+            OutputFormat::Markdown
+                .remove_entries(&file_io_spy, &mut contents, |name, version| {
+                    (name, version) != ("example", "1.0.0")
+                })
+                .unwrap()
+                .as_slice()
+        );
+        assert_eq!(
+            [
+                OutputFormat::Markdown.heading(Crate::columns()),
+                "|anyhow|1.0.100|\n|example|2.0.0|\n".to_string()
+            ]
+            .join(""),
+            contents
+        );
+        assert_eq!([contents.as_bytes().to_vec()], file_io_spy.write.arguments)
+    }
+
+    #[test]
+    fn csv_entries_not_kept_are_removed() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents = [
+            OutputFormat::Csv.heading(Crate::columns()),
+            "anyhow,1.0.100\r\nexample,1.0.0\r\n".to_string(),
+        ]
+        .join("");
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        OutputFormat::Csv
+            .remove_entries(&file_io_spy, &mut contents, |name, _| name == "anyhow")
+            .unwrap();
+
+        assert_eq!(
+            [
+                OutputFormat::Csv.heading(Crate::columns()),
+                "anyhow,1.0.100\r\n".to_string()
+            ]
+            .join(""),
+            contents
+        )
+    }
+
+    #[test]
+    fn json_entries_not_kept_are_removed() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents =
+            r#"[{"name": "anyhow", "version": "1.0.100"}, {"name": "example", "version": "1.0.0"}]"#
+                .to_string();
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        OutputFormat::Json
+            .remove_entries(&file_io_spy, &mut contents, |name, _| name == "anyhow")
+            .unwrap();
+
+        assert_eq!(
+            BTreeMap::from([(("anyhow".to_string(), "1.0.100".to_string()), None)]),
+            fetched(OutputFormat::Json.existing_crates(&contents).unwrap())
+        )
+    }
+
+    #[test]
+    fn output_file_is_not_written_when_all_entries_are_kept() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents = [
+            OutputFormat::Markdown.heading(Crate::columns()),
+            "|anyhow|1.0.100|\n".to_string(),
+        ]
+        .join("");
+
+        assert!(
+            OutputFormat::Markdown
+                .remove_entries(&file_io_spy, &mut contents, |_, _| true)
+                .unwrap()
+                .is_empty()
+        );
+        assert!(file_io_spy.write.arguments.take().is_empty())
+    }
+
+    #[test]
+    fn markdown_existing_crates_from_formatted_document() {
+        assert_eq!(
+            BTreeMap::from([
+                (("anyhow".to_string(), "1.0.100".to_string()), None),
+                (("syn".to_string(), "2.0.106".to_string()), None),
+            ]),
+            fetched(OutputFormat::Markdown
+                .existing_crates(
+                    "# Trust list\n\n| name   | version |\n|:-------|---------|\n| anyhow | 1.0.100 |\n| syn    | 2.0.106 |\n\n| name | notes |\n|-|-|\n| x | y |\n"
+                )
+                .unwrap())
+        )
+    }
+
+    #[test]
+    fn markdown_entry_is_inserted_before_content_after_table() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents = [
+            OutputFormat::Markdown.heading(Crate::columns()),
+            "|anyhow|1.0.100|\n\nReviewed quarterly.\n".to_string(),
+        ]
+        .join("");
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        OutputFormat::Markdown
+            .write_entry(
+                &file_io_spy,
+                &mut contents,
+                &example_crate(),
+                None,
+                &all_columns(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            [
+                OutputFormat::Markdown.heading(Crate::columns()),
+                "|anyhow|1.0.100|\n".to_string(),
+                example_crate().table_entry(),
+                "\nReviewed quarterly.\n".to_string()
+            ]
+            .join(""),
+            contents
+        );
+        assert_eq!([contents.as_bytes().to_vec()], file_io_spy.write.arguments)
+    }
+
+    fn heading_with_notes() -> String {
+        let heading = [Crate::columns(), &["notes"]].concat();
+        [
+            markdown::row(&heading),
+            markdown::row(&vec!["-"; heading.len()]),
+        ]
+        .join("")
+    }
+
+    #[test]
+    fn markdown_existing_crates_have_full_records() {
+        let contents = [
+            heading_with_notes(),
+            "|example|1.0.0|||||||||||reviewed|\n".to_string(),
+        ]
+        .join("");
+
+        let existing = OutputFormat::Markdown.existing_crates(&contents).unwrap();
+
+        assert_eq!(
+            Some(&Value::String("reviewed".to_string())),
+            existing[&("example".to_string(), "1.0.0".to_string())].get("notes")
+        )
+    }
+
+    #[test]
+    fn markdown_entry_keeps_user_cells_when_replaced() {
+        let file_io_spy = FileIOSpy::default();
+        let row = "| example | 1.0.0 |||||||||||  reviewed \\| ok  |\n";
+        let mut contents = [heading_with_notes(), row.to_string()].join("");
+        let existing = OutputFormat::Markdown.existing_crates(&contents).unwrap();
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        OutputFormat::Markdown
+            .write_entry(
+                &file_io_spy,
+                &mut contents,
+                &example_crate(),
+                existing.get(&("example".to_string(), "1.0.0".to_string())),
+                &all_columns(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            [
+                heading_with_notes(),
+                example_crate()
+                    .table_entry()
+                    .replace("|\n", "|reviewed \\| ok|\n")
+            ]
+            .join(""),
+            contents
+        )
+    }
+
+    #[test]
+    fn markdown_entry_has_empty_user_cells_when_appended() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents = heading_with_notes();
+
+        file_io_spy.append.returns.set([Ok(())]);
+
+        OutputFormat::Markdown
+            .write_entry(
+                &file_io_spy,
+                &mut contents,
+                &example_crate(),
+                None,
+                &all_columns(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            [example_crate()
+                .table_entry()
+                .replace("|\n", "||\n")
+                .into_bytes()],
+            file_io_spy.append.arguments
+        )
+    }
+
+    #[test]
+    fn markdown_recreate_keeps_user_columns_and_surrounding_content() {
+        let heading = markdown::row(&["notes", "name", "version", "downloads"]);
+        let contents =
+            format!("# Trust list\n\n{heading}|-|-|-|-|\n|ok|example|1.0.0|100|\n\nEnd.\n");
+
+        assert_eq!(
+            ["# Trust list\n\n", &heading_with_notes(), "\nEnd.\n"].join(""),
+            OutputFormat::Markdown.recreate(&contents, &all_columns())
+        )
+    }
+
+    #[test]
+    fn csv_recreate_keeps_user_columns() {
+        assert_eq!(
+            csv_record(&[Crate::columns(), &["notes"]].concat()),
+            OutputFormat::Csv
+                .recreate("name,notes,version\r\nexample,ok,1.0.0\r\n", &all_columns())
+        )
+    }
+
+    #[test]
+    fn csv_entry_keeps_user_fields_when_replaced() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents = [
+            csv_record(&[Crate::columns(), &["notes"]].concat()),
+            "example,1.0.0,,,,,,,,,,,\"reviewed, ok\"\r\n".to_string(),
+        ]
+        .join("");
+        let existing = OutputFormat::Csv.existing_crates(&contents).unwrap();
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        OutputFormat::Csv
+            .write_entry(
+                &file_io_spy,
+                &mut contents,
+                &example_crate(),
+                existing.get(&("example".to_string(), "1.0.0".to_string())),
+                &all_columns(),
+            )
+            .unwrap();
+
+        assert!(contents.ends_with(",1970-01-01T00:00:00Z,\"reviewed, ok\"\r\n"))
+    }
+
+    #[test]
+    fn json_entry_keeps_user_keys() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents =
+            r#"[{"name": "example", "version": "1.0.0", "reviewed": true}]"#.to_string();
+        let existing = OutputFormat::Json.existing_crates(&contents).unwrap();
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        OutputFormat::Json
+            .write_entry(
+                &file_io_spy,
+                &mut contents,
+                &example_crate(),
+                existing.get(&("example".to_string(), "1.0.0".to_string())),
+                &all_columns(),
+            )
+            .unwrap();
+
+        let document = serde_json::from_str::<Value>(&contents).unwrap();
+        assert_eq!(Value::Bool(true), document[0]["reviewed"]);
+        assert_eq!(Value::from(100), document[0]["downloads"])
+    }
+
+    fn columns(columns: &[&str]) -> Vec<String> {
+        select_columns(Some(
+            columns.iter().map(|column| column.to_string()).collect(),
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn all_columns_are_selected_by_default() {
+        assert_eq!(Crate::columns(), all_columns())
+    }
+
+    #[test]
+    fn selected_columns_keep_their_order() {
+        assert_eq!(
+            ["downloads", "name", "version"],
+            columns(&["downloads", " name", "version "]).as_slice()
+        )
+    }
+
+    #[test]
+    fn unknown_column_fails_to_select() {
+        assert_eq!(
+            "unknown column: stars, expected one of name, version, latest_version, license, downloads, contributors, reverse_dependencies, versions, created_at, updated_at, repository, fetched_at",
+            select_columns(Some(vec!["name".to_string(), "stars".to_string()]))
+                .unwrap_err()
+                .to_string()
+        )
+    }
+
+    #[test]
+    fn duplicate_column_fails_to_select() {
+        assert_eq!(
+            "duplicate column: name",
+            select_columns(Some(vec![
+                "name".to_string(),
+                "version".to_string(),
+                "name".to_string()
+            ]))
+            .unwrap_err()
+            .to_string()
+        )
+    }
+
+    #[test]
+    fn columns_without_version_fail_to_select() {
+        assert_eq!(
+            "columns must include version",
+            select_columns(Some(vec!["name".to_string()]))
+                .unwrap_err()
+                .to_string()
+        )
+    }
+
+    #[test]
+    fn heading_has_selected_columns() {
+        let selected = columns(&["name", "version", "downloads"]);
+
+        assert_eq!(
+            "|name|version|downloads|\n|-|-|-|\n",
+            OutputFormat::Markdown.heading(&selected)
+        );
+        assert_eq!(
+            "name,version,downloads\r\n",
+            OutputFormat::Csv.heading(&selected)
+        )
+    }
+
+    #[test]
+    fn markdown_entry_has_selected_columns() {
+        let file_io_spy = FileIOSpy::default();
+        let selected = columns(&["downloads", "name", "version"]);
+        let mut contents = OutputFormat::Markdown.heading(&selected);
+
+        file_io_spy.append.returns.set([Ok(())]);
+
+        OutputFormat::Markdown
+            .write_entry(
+                &file_io_spy,
+                &mut contents,
+                &example_crate(),
+                None,
+                &selected,
+            )
+            .unwrap();
+
+        assert!(contents.ends_with("|-|-|-|\n|100|example|1.0.0|\n"))
+    }
+
+    #[test]
+    fn json_entry_has_selected_columns_in_order() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents = OutputFormat::Json.heading(Crate::columns());
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        OutputFormat::Json
+            .write_entry(
+                &file_io_spy,
+                &mut contents,
+                &example_crate(),
+                None,
+                &columns(&["version", "name", "license"]),
+            )
+            .unwrap();
+
+        assert_eq!(
+            "[\n  {\n    \"version\": \"1.0.0\",\n    \"name\": \"example\",\n    \"license\": \"MIT OR Apache-2.0\"\n  }\n]\n",
+            contents
+        )
+    }
+
+    #[test]
+    fn recreate_uses_selected_columns_before_user_columns() {
+        assert_eq!(
+            "|name|version|downloads|notes|\n|-|-|-|-|\n",
+            OutputFormat::Markdown.recreate(
+                &[&heading_with_notes(), "|example|1.0.0|ok|\n"].join(""),
+                &columns(&["name", "version", "downloads"])
+            )
+        )
+    }
+
+    #[test]
+    fn markdown_entries_sort_by_number_keeping_content_after_table() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents =
+            "# Dependencies\n|name|version|contributors|\n|-|-|-|\n|a|1|30+|\n|b|1|3|\n|c|1||\n|d|1|12|\n\nOutro.\n"
+                .to_string();
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        OutputFormat::Markdown
+            .sort_entries(&file_io_spy, &mut contents, "contributors", false)
+            .unwrap();
+
+        assert_eq!(
+            "# Dependencies\n|name|version|contributors|\n|-|-|-|\n|b|1|3|\n|d|1|12|\n|a|1|30+|\n|c|1||\n\nOutro.\n",
+            contents
+        );
+        assert_eq!([contents.as_bytes().to_vec()], file_io_spy.write.arguments)
+    }
+
+    #[test]
+    fn markdown_entries_sort_by_version_components() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents = [
+            "|name|version|\n|-|-|\n",
+            "|a|1.9.0|\n|b|0.12.24|\n|c|1.10.0|\n|d|1.0.100|\n|e|0.9.0|\n|f|1.10.0-rc.1|\n",
+        ]
+        .join("");
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        OutputFormat::Markdown
+            .sort_entries(&file_io_spy, &mut contents, "version", false)
+            .unwrap();
+
+        assert_eq!(
+            [
+                "|name|version|\n|-|-|\n",
+                "|e|0.9.0|\n|b|0.12.24|\n|d|1.0.100|\n|a|1.9.0|\n|f|1.10.0-rc.1|\n|c|1.10.0|\n",
+            ]
+            .join(""),
+            contents
+        )
+    }
+
+    #[test]
+    fn markdown_entries_sort_by_date_descending_with_empty_cells_last() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents =
+            "|name|version|updated_at|\n|-|-|-|\n|a|1|01/02/2024|\n|b|1||\n|c|1|05/10/2025|\n|d|1|28/12/2024|"
+                .to_string();
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        OutputFormat::Markdown
+            .sort_entries(&file_io_spy, &mut contents, "updated_at", true)
+            .unwrap();
+
+        assert_eq!(
+            "|name|version|updated_at|\n|-|-|-|\n|c|1|05/10/2025|\n|d|1|28/12/2024|\n|a|1|01/02/2024|\n|b|1||\n",
+            contents
+        )
+    }
+
+    #[test]
+    fn sorted_entries_are_not_rewritten() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents = "|name|version|\n|-|-|\n|anyhow|1.0.100|\n|syn|2.0.106|\n".to_string();
+
+        OutputFormat::Markdown
+            .sort_entries(&file_io_spy, &mut contents, "name", false)
+            .unwrap();
+
+        assert!(file_io_spy.write.arguments.take().is_empty())
+    }
+
+    #[test]
+    fn sorting_by_missing_column_fails() {
+        assert_eq!(
+            "output file has no stars column",
+            OutputFormat::Markdown
+                .sort_entries(
+                    &FileIOSpy::default(),
+                    &mut "|name|version|\n|-|-|\n".to_string(),
+                    "stars",
+                    false
+                )
+                .unwrap_err()
+                .to_string()
+        )
+    }
+
+    #[test]
+    fn columns_are_checked_against_what_entries_will_have() {
+        let columns = columns(&["name", "version", "downloads"]);
+
+        assert_eq!(
+            [Ok(()), Err(MissingColumn("downloads".to_string())), Ok(())],
+            [
+                OutputFormat::Csv.check_column("name,version,notes\r\n", &columns, "notes"),
+                OutputFormat::Markdown.check_column(
+                    "|name|version|\n|-|-|\n",
+                    &columns,
+                    "downloads"
+                ),
+                OutputFormat::Json.check_column("[]\n", &columns, "downloads"),
+            ]
+        )
+    }
+
+    #[test]
+    fn csv_entries_sort_by_user_column() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents =
+            "name,version,notes\r\nanyhow,1.0.100,\"b, later\"\r\nsyn,2.0.106,a\r\n".to_string();
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        OutputFormat::Csv
+            .sort_entries(&file_io_spy, &mut contents, "notes", false)
+            .unwrap();
+
+        assert_eq!(
+            "name,version,notes\r\nsyn,2.0.106,a\r\nanyhow,1.0.100,\"b, later\"\r\n",
+            contents
+        )
+    }
+
+    #[test]
+    fn json_entries_sort_by_typed_values() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents = r#"[{"name": "anyhow", "downloads": 900, "versions": [1, 2]}, {"name": "syn", "downloads": 90, "versions": [1, 2, 3]}]"#.to_string();
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        OutputFormat::Json
+            .sort_entries(&file_io_spy, &mut contents, "downloads", false)
+            .unwrap();
+        assert_eq!(
+            Value::from("syn"),
+            serde_json::from_str::<Value>(&contents).unwrap()[0]["name"]
+        );
+
+        OutputFormat::Json
+            .sort_entries(&file_io_spy, &mut contents, "versions", true)
+            .unwrap();
+        assert_eq!(
+            Value::from("syn"),
+            serde_json::from_str::<Value>(&contents).unwrap()[0]["name"]
+        );
+        assert_eq!(1, file_io_spy.write.arguments.take().len())
+    }
+
+    #[test]
+    fn unknown_cells_sort_last() {
+        let file_io_spy = FileIOSpy::default();
+        let mut contents =
+            "|name|version|contributors|\n|-|-|-|\n|a|1|?|\n|b|1|n/a|\n|c|1|4|\n".to_string();
+
+        file_io_spy.write.returns.set([Ok(())]);
+
+        OutputFormat::Markdown
+            .sort_entries(&file_io_spy, &mut contents, "contributors", true)
+            .unwrap();
+
+        assert_eq!(
+            "|name|version|contributors|\n|-|-|-|\n|c|1|4|\n|a|1|?|\n|b|1|n/a|\n",
+            contents
+        )
+    }
+}
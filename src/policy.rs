@@ -0,0 +1,209 @@
+use crate::crates_io::Crate;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    min_downloads: Option<u64>,
+    min_contributors: Option<u16>,
+    min_reverse_dependencies: Option<u64>,
+    max_days_since_update: Option<i64>,
+    min_days_since_creation: Option<i64>,
+}
+
+impl Policy {
+    pub fn checks_reverse_dependencies(&self) -> bool {
+        self.min_reverse_dependencies.is_some()
+    }
+
+    pub fn violations(&self, crates: &[Crate], now: DateTime<Utc>) -> Vec<String> {
+        crates
+            .iter()
+            .flat_map(|crate_info| {
+                let name = format!("{} {}", crate_info.name, crate_info.version);
+                let days_since_update = (now - crate_info.updated_at).num_days();
+                let days_since_creation = (now - crate_info.created_at).num_days();
+
+                [
+                    self.min_downloads
+                        .filter(|minimum| crate_info.downloads < *minimum)
+                        .map(|minimum| {
+                            format!(
+                                "{name} has {} downloads, minimum is {minimum}",
+                                crate_info.downloads
+                            )
+                        }),
+                    // an unknown number of contributors is not a violation
+                    self.min_contributors
+                        .zip(crate_info.contributors.present())
+                        .filter(|(minimum, contributors)| *contributors < minimum)
+                        .map(|(minimum, contributors)| {
+                            format!("{name} has {contributors} contributors, minimum is {minimum}")
+                        }),
+                    self.min_reverse_dependencies
+                        .filter(|minimum| crate_info.reverse_dependencies < *minimum)
+                        .map(|minimum| {
+                            format!(
+                                "{name} has {} reverse dependencies, minimum is {minimum}",
+                                crate_info.reverse_dependencies
+                            )
+                        }),
+                    self.max_days_since_update
+                        .filter(|maximum| days_since_update > *maximum)
+                        .map(|maximum| {
+                            format!(
+                                "{name} was updated {days_since_update} days ago, maximum is {maximum}"
+                            )
+                        }),
+                    self.min_days_since_creation
+                        .filter(|minimum| days_since_creation < *minimum)
+                        .map(|minimum| {
+                            format!(
+                                "{name} was created {days_since_creation} days ago, minimum is {minimum}"
+                            )
+                        }),
+                ]
+            })
+            .flatten()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crates_io::Crate;
+    use crate::fetched::Fetched;
+    use crate::policy::Policy;
+    use chrono::DateTime;
+    use std::str::FromStr;
+
+    fn parse(contents: &str) -> anyhow::Result<Policy> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    fn example_crate() -> Crate {
+        Crate {
+            name: "example".to_string(),
+            version: "1.0.0".to_string(),
+            latest_version: Some("1.0.0".to_string()),
+            license: Some("MIT".to_string()),
+            downloads: 100,
+            contributors: Fetched::Present(2),
+            reverse_dependencies: 10,
+            versions: 2,
+            created_at: DateTime::from_str("2025-01-01T00:00:00Z").unwrap(),
+            updated_at: DateTime::from_str("2025-06-01T00:00:00Z").unwrap(),
+            repository: Fetched::Present("https://github.com/lhalf/trust-list".to_string()),
+            fetched_at: Default::default(),
+        }
+    }
+
+    #[test]
+    fn parses_all_thresholds() {
+        assert_eq!(
+            Policy {
+                min_downloads: Some(1000),
+                min_contributors: Some(2),
+                min_reverse_dependencies: Some(5),
+                max_days_since_update: Some(365),
+                min_days_since_creation: Some(90),
+            },
+            parse(
+                r#"
+                min_downloads = 1000
+                min_contributors = 2
+                min_reverse_dependencies = 5
+                max_days_since_update = 365
+                min_days_since_creation = 90
+                "#
+            )
+            .unwrap()
+        )
+    }
+
+    #[test]
+    fn empty_policy_has_no_thresholds() {
+        assert_eq!(Policy::default(), parse("").unwrap())
+    }
+
+    #[test]
+    fn unknown_threshold_fails_to_parse() {
+        assert!(
+            parse("min_stars = 10")
+                .unwrap_err()
+                .to_string()
+                .contains("unknown field `min_stars`")
+        )
+    }
+
+    #[test]
+    fn unknown_contributors_are_not_a_violation() {
+        let policy = parse("min_contributors = 3").unwrap();
+
+        assert!(
+            policy
+                .violations(
+                    &[Crate {
+                        contributors: Fetched::Failed("deliberate test error".to_string()),
+                        ..example_crate()
+                    }],
+                    DateTime::from_str("2025-07-01T00:00:00Z").unwrap()
+                )
+                .is_empty()
+        )
+    }
+
+    #[test]
+    fn crate_meeting_thresholds_has_no_violations() {
+        let policy = parse(
+            r#"
+            min_downloads = 100
+            min_contributors = 2
+            min_reverse_dependencies = 10
+            max_days_since_update = 30
+            min_days_since_creation = 90
+            "#,
+        )
+        .unwrap();
+
+        assert!(
+            policy
+                .violations(
+                    &[example_crate()],
+                    DateTime::from_str("2025-07-01T00:00:00Z").unwrap()
+                )
+                .is_empty()
+        )
+    }
+
+    #[test]
+    fn crate_failing_thresholds_reports_each_violation() {
+        let policy = parse(
+            r#"
+            min_downloads = 101
+            min_contributors = 3
+            min_reverse_dependencies = 11
+            max_days_since_update = 29
+            min_days_since_creation = 200
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            [
+                "example 1.0.0 has 100 downloads, minimum is 101",
+                "example 1.0.0 has 2 contributors, minimum is 3",
+                "example 1.0.0 has 10 reverse dependencies, minimum is 11",
+                "example 1.0.0 was updated 30 days ago, maximum is 29",
+                "example 1.0.0 was created 181 days ago, minimum is 200",
+            ],
+            policy
+                .violations(
+                    &[example_crate()],
+                    DateTime::from_str("2025-07-01T00:00:00Z").unwrap()
+                )
+                .as_slice()
+        )
+    }
+}
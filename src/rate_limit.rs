@@ -0,0 +1,354 @@
+use crate::http_client::{GetRequest, RateLimited, Response};
+use reqwest::Url;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+// crates.io api policy - https://crates.io/data-access#api
+const HOST_BUDGETS: [(&str, Duration); 2] = [
+    ("crates.io", Duration::from_secs(1)),
+    ("api.github.com", Duration::from_millis(250)),
+];
+
+#[cfg_attr(test, autospy::autospy)]
+pub trait Clock {
+    fn now(&self) -> Instant;
+    fn sleep(&self, duration: Duration);
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration)
+    }
+}
+
+/// The rate limits waited for, kept to report once the run finishes rather than over the progress bar
+pub type Waits = Arc<Mutex<Vec<String>>>;
+
+/// Spaces out requests to each host by its budget, requests to different hosts are not delayed
+pub struct RateLimitedClient<C, K> {
+    client: C,
+    clock: K,
+    next_request: Mutex<HashMap<&'static str, Instant>>,
+    /// Retry a request refused by a host's rate limit once the limit resets
+    wait_for_reset: bool,
+    waits: Waits,
+}
+
+impl<C: GetRequest, K: Clock> RateLimitedClient<C, K> {
+    pub fn new(client: C, clock: K, wait_for_reset: bool, waits: Waits) -> Self {
+        Self {
+            client,
+            clock,
+            next_request: Mutex::new(HashMap::new()),
+            wait_for_reset,
+            waits,
+        }
+    }
+
+    fn reserve(&self, url: &str) -> Option<Duration> {
+        let host = Url::parse(url).ok()?.host_str()?.to_string();
+        let (host, interval) = HOST_BUDGETS
+            .into_iter()
+            .find(|(budget_host, _)| *budget_host == host)?;
+
+        let now = self.clock.now();
+        let mut next_request = self
+            .next_request
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        let slot = next_request
+            .get(host)
+            .copied()
+            .filter(|slot| *slot > now)
+            .unwrap_or(now);
+        next_request.insert(host, slot + interval);
+
+        Some(slot - now).filter(|wait| !wait.is_zero())
+    }
+
+    fn request<T>(&self, url: &str, send: impl Fn() -> anyhow::Result<T>) -> anyhow::Result<T> {
+        if let Some(wait) = self.reserve(url) {
+            self.clock.sleep(wait);
+        }
+
+        let error = match send() {
+            Err(error) if self.wait_for_reset => error,
+            result => return result,
+        };
+        match error
+            .downcast_ref::<RateLimited>()
+            .and_then(|rate_limited| rate_limited.retry_after)
+        {
+            Some(retry_after) => {
+                self.waits
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner)
+                    .push(error.to_string());
+                self.clock.sleep(retry_after);
+                send()
+            }
+            None => Err(error),
+        }
+    }
+}
+
+impl<C: GetRequest, K: Clock> GetRequest for RateLimitedClient<C, K> {
+    fn get(&self, url: &str) -> Result<String, anyhow::Error> {
+        self.request(url, || self.client.get(url))
+    }
+
+    fn get_response(&self, url: &str) -> Result<Response, anyhow::Error> {
+        self.request(url, || self.client.get_response(url))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::http_client::{GetRequest, GetRequestSpy, RateLimited};
+    use crate::rate_limit::{ClockSpy, RateLimitedClient, Waits};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn first_request_to_host_is_not_delayed() {
+        let http_client_spy = GetRequestSpy::default();
+        let clock_spy = ClockSpy::default();
+        let start = Instant::now();
+
+        clock_spy.now.returns.set([start]);
+        http_client_spy
+            .get
+            .returns
+            .set([Ok("response".to_string())]);
+
+        let client =
+            RateLimitedClient::new(http_client_spy, clock_spy.clone(), false, Waits::default());
+
+        assert_eq!(
+            "response",
+            client
+                .get("https://crates.io/api/v1/crates/autospy")
+                .unwrap()
+        );
+        assert!(clock_spy.sleep.arguments.take().is_empty())
+    }
+
+    #[test]
+    fn consecutive_requests_to_crates_io_wait_for_budget() {
+        let http_client_spy = GetRequestSpy::default();
+        let clock_spy = ClockSpy::default();
+        let start = Instant::now();
+
+        clock_spy
+            .now
+            .returns
+            .set([start, start + Duration::from_millis(200)]);
+        clock_spy.sleep.returns.set([()]);
+        http_client_spy
+            .get
+            .returns
+            .set([Ok(String::new()), Ok(String::new())]);
+
+        let client =
+            RateLimitedClient::new(http_client_spy, clock_spy.clone(), false, Waits::default());
+
+        client
+            .get("https://crates.io/api/v1/crates/autospy")
+            .unwrap();
+        client
+            .get("https://crates.io/api/v1/crates/autospy/reverse_dependencies")
+            .unwrap();
+
+        assert_eq!([Duration::from_millis(800)], clock_spy.sleep.arguments)
+    }
+
+    #[test]
+    fn queued_requests_to_crates_io_are_spaced_by_budget() {
+        let http_client_spy = GetRequestSpy::default();
+        let clock_spy = ClockSpy::default();
+        let start = Instant::now();
+
+        clock_spy.now.returns.set([start, start, start]);
+        clock_spy.sleep.returns.set([(), ()]);
+        http_client_spy
+            .get
+            .returns
+            .set([Ok(String::new()), Ok(String::new()), Ok(String::new())]);
+
+        let client =
+            RateLimitedClient::new(http_client_spy, clock_spy.clone(), false, Waits::default());
+
+        for _ in 0..3 {
+            client
+                .get("https://crates.io/api/v1/crates/autospy")
+                .unwrap();
+        }
+
+        assert_eq!(
+            [Duration::from_secs(1), Duration::from_secs(2)],
+            clock_spy.sleep.arguments
+        )
+    }
+
+    #[test]
+    fn github_requests_have_separate_budget_from_crates_io() {
+        let http_client_spy = GetRequestSpy::default();
+        let clock_spy = ClockSpy::default();
+        let start = Instant::now();
+
+        clock_spy.now.returns.set([start, start]);
+        http_client_spy
+            .get
+            .returns
+            .set([Ok(String::new()), Ok(String::new())]);
+
+        let client =
+            RateLimitedClient::new(http_client_spy, clock_spy.clone(), false, Waits::default());
+
+        client
+            .get("https://crates.io/api/v1/crates/autospy")
+            .unwrap();
+        client
+            .get("https://api.github.com/repos/lhalf/autospy/contributors")
+            .unwrap();
+
+        assert!(clock_spy.sleep.arguments.take().is_empty())
+    }
+
+    #[test]
+    fn requests_to_hosts_without_budget_are_not_limited() {
+        let http_client_spy = GetRequestSpy::default();
+        let clock_spy = ClockSpy::default();
+
+        http_client_spy
+            .get
+            .returns
+            .set([Ok(String::new()), Ok(String::new())]);
+
+        let client =
+            RateLimitedClient::new(http_client_spy, clock_spy.clone(), false, Waits::default());
+
+        client.get("https://example.com/a").unwrap();
+        client.get("https://example.com/b").unwrap();
+
+        assert!(clock_spy.sleep.arguments.take().is_empty())
+    }
+
+    #[test]
+    fn failed_requests_still_use_budget() {
+        let http_client_spy = GetRequestSpy::default();
+        let clock_spy = ClockSpy::default();
+        let start = Instant::now();
+
+        clock_spy.now.returns.set([start, start]);
+        clock_spy.sleep.returns.set([()]);
+        http_client_spy.get.returns.set([
+            Err(anyhow::anyhow!("deliberate test error")),
+            Ok(String::new()),
+        ]);
+
+        let client =
+            RateLimitedClient::new(http_client_spy, clock_spy.clone(), false, Waits::default());
+
+        assert_eq!(
+            "deliberate test error",
+            client
+                .get("https://crates.io/api/v1/crates/autospy")
+                .unwrap_err()
+                .to_string()
+        );
+        client
+            .get("https://crates.io/api/v1/crates/autospy")
+            .unwrap();
+
+        assert_eq!([Duration::from_secs(1)], clock_spy.sleep.arguments)
+    }
+
+    fn rate_limited(retry_after: Option<Duration>) -> anyhow::Error {
+        RateLimited {
+            url: "https://api.github.com/repos/lhalf/autospy/contributors".to_string(),
+            retry_after,
+            authenticated: false,
+        }
+        .into()
+    }
+
+    #[test]
+    fn rate_limited_request_is_retried_after_reset() {
+        let http_client_spy = GetRequestSpy::default();
+        let clock_spy = ClockSpy::default();
+
+        clock_spy.now.returns.set([Instant::now()]);
+        clock_spy.sleep.returns.set([()]);
+        http_client_spy.get.returns.set([
+            Err(rate_limited(Some(Duration::from_secs(90)))),
+            Ok("response".to_string()),
+        ]);
+
+        let waits = Waits::default();
+        let client =
+            RateLimitedClient::new(http_client_spy, clock_spy.clone(), true, waits.clone());
+
+        assert_eq!(
+            "response",
+            client
+                .get("https://api.github.com/repos/lhalf/autospy/contributors")
+                .unwrap()
+        );
+        assert_eq!([Duration::from_secs(90)], clock_spy.sleep.arguments);
+        assert_eq!(
+            [rate_limited(Some(Duration::from_secs(90))).to_string()],
+            waits.lock().unwrap().as_slice()
+        )
+    }
+
+    #[test]
+    fn rate_limited_request_without_reset_is_not_retried() {
+        let http_client_spy = GetRequestSpy::default();
+        let clock_spy = ClockSpy::default();
+
+        clock_spy.now.returns.set([Instant::now()]);
+        http_client_spy.get.returns.set([Err(rate_limited(None))]);
+
+        let client =
+            RateLimitedClient::new(http_client_spy, clock_spy.clone(), true, Waits::default());
+
+        assert!(
+            client
+                .get("https://api.github.com/repos/lhalf/autospy/contributors")
+                .unwrap_err()
+                .is::<RateLimited>()
+        );
+        assert!(clock_spy.sleep.arguments.take().is_empty())
+    }
+
+    #[test]
+    fn rate_limited_request_is_not_retried_unless_waiting() {
+        let http_client_spy = GetRequestSpy::default();
+        let clock_spy = ClockSpy::default();
+
+        clock_spy.now.returns.set([Instant::now()]);
+        http_client_spy
+            .get
+            .returns
+            .set([Err(rate_limited(Some(Duration::from_secs(90))))]);
+
+        let client =
+            RateLimitedClient::new(http_client_spy, clock_spy.clone(), false, Waits::default());
+
+        assert!(
+            client
+                .get("https://api.github.com/repos/lhalf/autospy/contributors")
+                .unwrap_err()
+                .is::<RateLimited>()
+        );
+        assert!(clock_spy.sleep.arguments.take().is_empty())
+    }
+}
@@ -0,0 +1,277 @@
+use crate::http_client::{GetRequest, RateLimited, Response, StatusError};
+use crate::rate_limit::Clock;
+use std::hash::BuildHasher;
+use std::time::Duration;
+
+const BASE_DELAY: Duration = Duration::from_millis(500);
+// longer waits, such as GitHub's hourly rate limit reset, are left to --wait-for-rate-limit
+const MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// Retries requests that failed for a reason that may pass, backing off exponentially between attempts
+pub struct RetryingClient<C, K> {
+    client: C,
+    clock: K,
+    retries: u8,
+}
+
+impl<C: GetRequest, K: Clock> RetryingClient<C, K> {
+    pub fn new(client: C, clock: K, retries: u8) -> Self {
+        Self {
+            client,
+            clock,
+            retries,
+        }
+    }
+
+    fn request<T>(&self, url: &str, send: impl Fn() -> anyhow::Result<T>) -> anyhow::Result<T> {
+        let mut attempt = 0;
+        loop {
+            let error = match send() {
+                Err(error) if attempt < self.retries => error,
+                result => return result,
+            };
+            let Some(retry_after) = transient(&error) else {
+                return Err(error);
+            };
+            self.clock
+                .sleep(retry_after.unwrap_or_else(|| backoff(url, attempt)));
+            attempt += 1;
+        }
+    }
+}
+
+impl<C: GetRequest, K: Clock> GetRequest for RetryingClient<C, K> {
+    fn get(&self, url: &str) -> Result<String, anyhow::Error> {
+        self.request(url, || self.client.get(url))
+    }
+
+    fn get_response(&self, url: &str) -> Result<Response, anyhow::Error> {
+        self.request(url, || self.client.get_response(url))
+    }
+}
+
+/// Whether retrying may succeed, with how long the host asked to wait if it did
+fn transient(error: &anyhow::Error) -> Option<Option<Duration>> {
+    if let Some(rate_limited) = error.downcast_ref::<RateLimited>() {
+        return match rate_limited.retry_after {
+            Some(retry_after) if retry_after > MAX_DELAY => None,
+            retry_after => Some(retry_after),
+        };
+    }
+    if let Some(status_error) = error.downcast_ref::<StatusError>() {
+        return status_error.status.is_server_error().then_some(
+            status_error
+                .retry_after
+                .map(|retry_after| retry_after.min(MAX_DELAY)),
+        );
+    }
+    // connection failures, timeouts and interrupted responses
+    error
+        .downcast_ref::<reqwest::Error>()
+        .filter(|error| !error.is_builder())
+        .map(|_| None)
+}
+
+/// Doubles with each attempt up to the maximum, jittered so concurrent retries spread out
+fn backoff(url: &str, attempt: u8) -> Duration {
+    let delay = BASE_DELAY
+        .saturating_mul(2_u32.saturating_pow(attempt.into()))
+        .min(MAX_DELAY);
+    let jitter = std::collections::hash_map::RandomState::new().hash_one((url, attempt)) % 1000;
+    delay.mul_f64(0.5 + jitter as f64 / 2000.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::http_client::{GetRequest, GetRequestSpy, RateLimited, Response, StatusError};
+    use crate::rate_limit::ClockSpy;
+    use crate::retry::{MAX_DELAY, RetryingClient, backoff};
+    use reqwest::StatusCode;
+    use std::time::Duration;
+
+    const URL: &str = "https://crates.io/api/v1/crates/autospy";
+
+    fn status_error(status: StatusCode, retry_after: Option<Duration>) -> anyhow::Error {
+        StatusError {
+            url: URL.to_string(),
+            status,
+            retry_after,
+        }
+        .into()
+    }
+
+    #[test]
+    fn successful_request_is_not_retried() {
+        let http_client_spy = GetRequestSpy::default();
+        let clock_spy = ClockSpy::default();
+
+        http_client_spy
+            .get
+            .returns
+            .set([Ok("response".to_string())]);
+
+        let client = RetryingClient::new(http_client_spy, clock_spy.clone(), 3);
+
+        assert_eq!("response", client.get(URL).unwrap());
+        assert!(clock_spy.sleep.arguments.take().is_empty())
+    }
+
+    #[test]
+    fn server_errors_are_retried_with_backoff() {
+        let http_client_spy = GetRequestSpy::default();
+        let clock_spy = ClockSpy::default();
+
+        http_client_spy.get.returns.set([
+            Err(status_error(StatusCode::BAD_GATEWAY, None)),
+            Err(status_error(StatusCode::SERVICE_UNAVAILABLE, None)),
+            Ok("response".to_string()),
+        ]);
+        clock_spy.sleep.returns.set([(), ()]);
+
+        let client = RetryingClient::new(http_client_spy, clock_spy.clone(), 3);
+
+        assert_eq!("response", client.get(URL).unwrap());
+
+        let [first, second] = clock_spy.sleep.arguments.take().try_into().unwrap();
+        assert!((Duration::from_millis(250)..=Duration::from_millis(500)).contains(&first));
+        assert!((Duration::from_millis(500)..=Duration::from_secs(1)).contains(&second))
+    }
+
+    #[test]
+    fn retry_after_is_honoured() {
+        let http_client_spy = GetRequestSpy::default();
+        let clock_spy = ClockSpy::default();
+
+        http_client_spy.get_response.returns.set([
+            Err(status_error(
+                StatusCode::SERVICE_UNAVAILABLE,
+                Some(Duration::from_secs(7)),
+            )),
+            Ok(Response::default()),
+        ]);
+        clock_spy.sleep.returns.set([()]);
+
+        let client = RetryingClient::new(http_client_spy, clock_spy.clone(), 3);
+
+        assert_eq!(Response::default(), client.get_response(URL).unwrap());
+        assert_eq!([Duration::from_secs(7)], clock_spy.sleep.arguments)
+    }
+
+    #[test]
+    fn short_rate_limits_are_retried() {
+        let http_client_spy = GetRequestSpy::default();
+        let clock_spy = ClockSpy::default();
+
+        http_client_spy.get.returns.set([
+            Err(RateLimited {
+                url: URL.to_string(),
+                retry_after: Some(Duration::from_secs(2)),
+                authenticated: false,
+            }
+            .into()),
+            Ok("response".to_string()),
+        ]);
+        clock_spy.sleep.returns.set([()]);
+
+        let client = RetryingClient::new(http_client_spy, clock_spy.clone(), 3);
+
+        assert_eq!("response", client.get(URL).unwrap());
+        assert_eq!([Duration::from_secs(2)], clock_spy.sleep.arguments)
+    }
+
+    #[test]
+    fn long_rate_limits_are_not_retried() {
+        let http_client_spy = GetRequestSpy::default();
+        let clock_spy = ClockSpy::default();
+
+        http_client_spy.get.returns.set([Err(RateLimited {
+            url: URL.to_string(),
+            retry_after: Some(MAX_DELAY + Duration::from_secs(1)),
+            authenticated: false,
+        }
+        .into())]);
+
+        let client = RetryingClient::new(http_client_spy, clock_spy.clone(), 3);
+
+        assert!(client.get(URL).unwrap_err().is::<RateLimited>());
+        assert!(clock_spy.sleep.arguments.take().is_empty())
+    }
+
+    #[test]
+    fn client_errors_are_not_retried() {
+        let http_client_spy = GetRequestSpy::default();
+        let clock_spy = ClockSpy::default();
+
+        http_client_spy
+            .get
+            .returns
+            .set([Err(status_error(StatusCode::NOT_FOUND, None))]);
+
+        let client = RetryingClient::new(http_client_spy, clock_spy.clone(), 3);
+
+        assert_eq!(
+            "invalid response from: https://crates.io/api/v1/crates/autospy, 404 Not Found",
+            client.get(URL).unwrap_err().to_string()
+        );
+        assert!(clock_spy.sleep.arguments.take().is_empty())
+    }
+
+    #[test]
+    fn other_errors_are_not_retried() {
+        let http_client_spy = GetRequestSpy::default();
+        let clock_spy = ClockSpy::default();
+
+        http_client_spy
+            .get
+            .returns
+            .set([Err(anyhow::anyhow!("deliberate test error"))]);
+
+        let client = RetryingClient::new(http_client_spy, clock_spy.clone(), 3);
+
+        assert!(client.get(URL).is_err());
+        assert!(clock_spy.sleep.arguments.take().is_empty())
+    }
+
+    #[test]
+    fn last_error_is_returned_once_retries_run_out() {
+        let http_client_spy = GetRequestSpy::default();
+        let clock_spy = ClockSpy::default();
+
+        http_client_spy.get.returns.set([
+            Err(status_error(StatusCode::BAD_GATEWAY, None)),
+            Err(status_error(StatusCode::BAD_GATEWAY, None)),
+            Err(status_error(StatusCode::INTERNAL_SERVER_ERROR, None)),
+        ]);
+        clock_spy.sleep.returns.set([(), ()]);
+
+        let client = RetryingClient::new(http_client_spy, clock_spy.clone(), 2);
+
+        assert_eq!(
+            "invalid response from: https://crates.io/api/v1/crates/autospy, 500 Internal Server Error",
+            client.get(URL).unwrap_err().to_string()
+        );
+        assert_eq!(2, clock_spy.sleep.arguments.take().len())
+    }
+
+    #[test]
+    fn no_retries_makes_a_single_attempt() {
+        let http_client_spy = GetRequestSpy::default();
+        let clock_spy = ClockSpy::default();
+
+        http_client_spy
+            .get
+            .returns
+            .set([Err(status_error(StatusCode::BAD_GATEWAY, None))]);
+
+        let client = RetryingClient::new(http_client_spy, clock_spy.clone(), 0);
+
+        assert!(client.get(URL).is_err());
+        assert!(clock_spy.sleep.arguments.take().is_empty())
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        assert!(backoff(URL, 20) <= MAX_DELAY);
+        assert!(backoff(URL, 20) >= MAX_DELAY / 2)
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+
+/// Runs `work` on up to `jobs` threads, results are passed to `handle` in the order of `items`
+pub fn run_in_order<I: Sync, T: Send>(
+    items: &[I],
+    jobs: usize,
+    work: impl Fn(&I) -> T + Sync,
+    mut handle: impl FnMut(&I, T) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let next_item = AtomicUsize::new(0);
+
+    std::thread::scope(|scope| {
+        // created inside the scope so workers stop sending once handle fails
+        let (sender, receiver) = mpsc::channel();
+
+        for _ in 0..jobs.clamp(1, items.len().max(1)) {
+            let sender = sender.clone();
+            let (next_item, work) = (&next_item, &work);
+            scope.spawn(move || {
+                loop {
+                    let index = next_item.fetch_add(1, Ordering::Relaxed);
+                    let Some(item) = items.get(index) else {
+                        break;
+                    };
+                    if sender.send((index, work(item))).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        let mut pending = BTreeMap::new();
+        let mut next_index = 0;
+
+        for (index, result) in receiver {
+            pending.insert(index, result);
+            while let Some(result) = pending.remove(&next_index) {
+                handle(&items[next_index], result)?;
+                next_index += 1;
+            }
+        }
+
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::scheduler::run_in_order;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    #[test]
+    fn no_items_are_handled() {
+        let mut handled = Vec::new();
+
+        run_in_order(
+            &Vec::<u64>::new(),
+            4,
+            |item| *item,
+            |_, result| {
+                handled.push(result);
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        assert!(handled.is_empty())
+    }
+
+    #[test]
+    fn results_are_handled_in_item_order_when_completed_out_of_order() {
+        let mut handled = Vec::new();
+
+        run_in_order(
+            &[40, 30, 20, 10, 0],
+            5,
+            |millis| {
+                std::thread::sleep(Duration::from_millis(*millis));
+                millis * 2
+            },
+            |item, result| {
+                handled.push((*item, result));
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            vec![(40, 80), (30, 60), (20, 40), (10, 20), (0, 0)],
+            handled
+        )
+    }
+
+    #[test]
+    fn work_runs_concurrently() {
+        let running = Mutex::new((0, 0));
+
+        run_in_order(
+            &[0; 4],
+            4,
+            |_| {
+                {
+                    let mut running = running.lock().unwrap();
+                    running.0 += 1;
+                    running.1 = running.1.max(running.0);
+                }
+                std::thread::sleep(Duration::from_millis(50));
+                running.lock().unwrap().0 -= 1;
+            },
+            |_, _| Ok(()),
+        )
+        .unwrap();
+
+        assert!(running.lock().unwrap().1 > 1)
+    }
+
+    #[test]
+    fn handle_failing_stops_remaining_items() {
+        let mut handled = Vec::new();
+
+        assert_eq!(
+            "deliberate test error",
+            run_in_order(
+                &[0, 1, 2, 3],
+                1,
+                |item| *item,
+                |item, _| match item {
+                    1 => Err(anyhow::anyhow!("deliberate test error")),
+                    _ => {
+                        handled.push(*item);
+                        Ok(())
+                    }
+                },
+            )
+            .unwrap_err()
+            .to_string()
+        );
+        assert_eq!(vec![0], handled)
+    }
+}